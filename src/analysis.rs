@@ -0,0 +1,406 @@
+// Questions about the language an automaton accepts.  Those that need the minimal DFA are answered by `Language`,
+// from one minimization.  The `FA` methods for them minimize on every call, so to ask several, build one `Language`.
+
+use crate::fa::{State, FA};
+
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+impl FA {
+    // A shortest string the automaton accepts, choosing the alphabetically least among those of that length.
+    // Returns `None` if the language is empty.
+    pub fn shortest_accepted(&self) -> Option<String> {
+        let dfa = self.to_dfa();
+        let alphabet = dfa.alphabet();
+
+        let mut parent: HashMap<State, Option<(State, char)>> = HashMap::new();
+        parent.insert(dfa.starting(), None);
+        let mut todo = VecDeque::new();
+        todo.push_back(dfa.starting());
+
+        while let Some(state) = todo.pop_front() {
+            if dfa.is_accepting(state) {
+                let mut string = Vec::new();
+                let mut cur = state;
+                while let Some(&Some((prev, c))) = parent.get(&cur) {
+                    string.push(c);
+                    cur = prev;
+                }
+                return Some(string.into_iter().rev().collect());
+            }
+            for &c in &alphabet {
+                if let Some(next) = dfa.next_state(state, c) {
+                    if let Entry::Vacant(entry) = parent.entry(next) {
+                        entry.insert(Some((state, c)));
+                        todo.push_back(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+    // Whether the automaton accepts no strings at all.
+    pub fn is_empty(&self) -> bool {
+        self.shortest_accepted().is_none()
+    }
+    // The length of the shortest accepted string, if any.
+    pub fn shortest_length(&self) -> Option<usize> {
+        self.shortest_accepted().map(|s| s.chars().count())
+    }
+    // Whether the automaton accepts only finitely many strings.
+    pub fn is_finite(&self) -> bool {
+        Language::new(self).is_finite()
+    }
+    // The length of the longest accepted string.  `None` if the language is empty or infinite.
+    pub fn longest_length(&self) -> Option<usize> {
+        Language::new(self).longest_length()
+    }
+    // How many strings of exactly `n` characters the automaton accepts.
+    pub fn count_of_length(&self, n: usize) -> Count {
+        Language::new(self).count_of_length(n)
+    }
+    // The total number of accepted strings, or `None` if there are infinitely many.
+    pub fn count(&self) -> Option<Count> {
+        Language::new(self).count()
+    }
+    // The states in an order where every transition goes forward, or `None` if there is a cycle.
+    fn topological_order(&self) -> Option<Vec<State>> {
+        let mut indegree: HashMap<State, usize> = self.states().iter().map(|&s| (s, 0)).collect();
+        for t in self.delta() {
+            *indegree.entry(t.end()).or_insert(0) += 1;
+        }
+        let mut ready = indegree
+            .iter()
+            .filter(|(_, &d)| d == 0)
+            .map(|(&s, _)| s)
+            .collect::<Vec<State>>();
+        let mut order = Vec::new();
+
+        while let Some(state) = ready.pop() {
+            order.push(state);
+            for t in self.transitions_of(state).into_iter().flatten() {
+                let d = indegree.get_mut(&t.end()).unwrap();
+                *d -= 1;
+                if *d == 0 {
+                    ready.push(t.end());
+                }
+            }
+        }
+
+        if order.len() == indegree.len() {
+            Some(order)
+        } else {
+            None
+        }
+    }
+}
+
+// The number of strings of a given length can outgrow any fixed-width integer (printable ASCII passes 2^128 at
+// length 20), and rounding would bias sampling, so counts are kept exactly as little-endian 64-bit limbs.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Count {
+    limbs: Vec<u64>,
+}
+
+impl Count {
+    pub fn from_limbs(mut limbs: Vec<u64>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        Self { limbs }
+    }
+    // Least significant first, with no zero limbs at the top.
+    pub fn limbs(&self) -> &[u64] {
+        &self.limbs
+    }
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+    pub fn bits(&self) -> usize {
+        self.limbs.last().map_or(0, |top| {
+            64 * self.limbs.len() - top.leading_zeros() as usize
+        })
+    }
+    pub fn add(&self, other: &Count) -> Count {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0u128;
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = carry
+                + *self.limbs.get(i).unwrap_or(&0) as u128
+                + *other.limbs.get(i).unwrap_or(&0) as u128;
+            limbs.push(sum as u64);
+            carry = sum >> 64;
+        }
+        limbs.push(carry as u64);
+        Count::from_limbs(limbs)
+    }
+    // `self - other`, where `other` is no larger.
+    pub fn sub(&self, other: &Count) -> Count {
+        let mut borrow = false;
+        let limbs = self
+            .limbs
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                let (d1, o1) = x.overflowing_sub(*other.limbs.get(i).unwrap_or(&0));
+                let (d2, o2) = d1.overflowing_sub(borrow as u64);
+                borrow = o1 || o2;
+                d2
+            })
+            .collect();
+        Count::from_limbs(limbs)
+    }
+    // Divides in place by a small divisor, returning the remainder.
+    fn div_rem(&mut self, divisor: u64) -> u64 {
+        let mut rem = 0u128;
+        for limb in self.limbs.iter_mut().rev() {
+            let cur = (rem << 64) | *limb as u128;
+            *limb = (cur / divisor as u128) as u64;
+            rem = cur % divisor as u128;
+        }
+        *self = Count::from_limbs(std::mem::take(&mut self.limbs));
+        rem as u64
+    }
+}
+
+impl From<u64> for Count {
+    fn from(n: u64) -> Self {
+        Count::from_limbs(vec![n])
+    }
+}
+
+impl Ord for Count {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for Count {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for Count {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Peel off nineteen decimal digits at a time, the most that fit in a u64.
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        let mut n = self.clone();
+        let mut chunks = Vec::new();
+        while !n.is_zero() {
+            chunks.push(n.div_rem(CHUNK));
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:019}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+// The questions that need the minimal DFA, answered from one minimization.
+#[derive(Debug, Clone)]
+pub struct Language {
+    dfa: FA,
+    // paths[k][q] is the number of strings of length k that lead from q to acceptance.  Filled in as longer lengths
+    // are asked for.
+    paths: Vec<HashMap<State, Count>>,
+}
+
+impl Language {
+    pub fn new(fa: &FA) -> Self {
+        let mut dfa = fa.clone();
+        dfa.minimize();
+        let accepting = dfa
+            .accepting()
+            .iter()
+            .map(|&s| (s, Count::from(1)))
+            .collect();
+        Self {
+            dfa,
+            paths: vec![accepting],
+        }
+    }
+    pub fn dfa(&self) -> &FA {
+        &self.dfa
+    }
+    // Minimization leaves no accepting states when there is nothing to accept.
+    pub fn is_empty(&self) -> bool {
+        self.dfa.accepting().is_empty()
+    }
+    // A language is finite exactly when its minimal DFA has no cycles: minimization leaves only states that are
+    // reachable and can still reach acceptance, so any cycle among them pumps out infinitely many strings.
+    pub fn is_finite(&self) -> bool {
+        self.dfa.topological_order().is_some()
+    }
+    // The length of the longest accepted string.  `None` if the language is empty or infinite.
+    pub fn longest_length(&self) -> Option<usize> {
+        let dfa = &self.dfa;
+        let order = dfa.topological_order()?;
+
+        // Go through the states in reverse topological order, so every successor is done before its predecessors.
+        let mut longest: HashMap<State, Option<usize>> = HashMap::new();
+        for &state in order.iter().rev() {
+            let mut best = if dfa.is_accepting(state) {
+                Some(0)
+            } else {
+                None
+            };
+            for t in dfa.transitions_of(state).into_iter().flatten() {
+                if let Some(rest) = longest[&t.end()] {
+                    best = best.max(Some(rest + 1));
+                }
+            }
+            longest.insert(state, best);
+        }
+        longest[&dfa.starting()]
+    }
+    // The number of strings of length `k` that lead from `state` to acceptance, by counting paths through the DFA:
+    // it is the sum, over the state's transitions, of the strings of length k - 1 that lead from their targets.
+    pub fn paths(&mut self, k: usize, state: State) -> Count {
+        while self.paths.len() <= k {
+            let shorter = self.paths.last().unwrap();
+            let mut longer = HashMap::new();
+            for &q in self.dfa.states() {
+                let mut sum = Count::default();
+                for t in self.dfa.transitions_of(q).into_iter().flatten() {
+                    if let Some(p) = shorter.get(&t.end()) {
+                        sum = sum.add(p);
+                    }
+                }
+                if !sum.is_zero() {
+                    longer.insert(q, sum);
+                }
+            }
+            self.paths.push(longer);
+        }
+        self.paths[k].get(&state).cloned().unwrap_or_default()
+    }
+    // How many strings of exactly `n` characters the automaton accepts.
+    pub fn count_of_length(&mut self, n: usize) -> Count {
+        self.paths(n, self.dfa.starting())
+    }
+    // The number of accepted strings of each length from 0 to `n`.
+    pub fn counts_up_to(&mut self, n: usize) -> Vec<Count> {
+        (0..=n).map(|k| self.count_of_length(k)).collect()
+    }
+    // The total number of accepted strings, or `None` if there are infinitely many.
+    pub fn count(&mut self) -> Option<Count> {
+        if self.is_empty() {
+            return Some(Count::default());
+        }
+        let longest = self.longest_length()?;
+        Some(
+            self.counts_up_to(longest)
+                .iter()
+                .fold(Count::default(), |sum, c| sum.add(c)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex_parser::parse_to_nfa;
+
+    fn counts(counts: &[u64]) -> Vec<Count> {
+        counts.iter().map(|&c| Count::from(c)).collect()
+    }
+
+    fn language(regex: &str) -> Language {
+        Language::new(&parse_to_nfa(regex).unwrap())
+    }
+
+    #[test]
+    fn count_add_carries() {
+        let sum = Count::from(u64::MAX).add(&Count::from(1));
+        assert_eq!(sum, Count::from_limbs(vec![0, 1]));
+        assert_eq!(sum.bits(), 65);
+        assert_eq!(sum.to_string(), "18446744073709551616");
+        let sum = Count::from_limbs(vec![u64::MAX, u64::MAX]).add(&Count::from(1));
+        assert_eq!(sum.limbs(), &[0, 0, 1]);
+    }
+
+    #[test]
+    fn count_sub_borrows() {
+        let difference = Count::from_limbs(vec![0, 1]).sub(&Count::from(1));
+        assert_eq!(difference, Count::from(u64::MAX));
+        let difference = Count::from_limbs(vec![0, 0, 1]).sub(&Count::from_limbs(vec![0, 0, 1]));
+        assert!(difference.is_zero());
+    }
+
+    #[test]
+    fn count_order_and_display() {
+        assert_eq!(Count::from_limbs(vec![5, 0, 0]), Count::from(5));
+        assert!(Count::from_limbs(vec![0, 1]) > Count::from(u64::MAX));
+        assert!(Count::from_limbs(vec![1, 2]) < Count::from_limbs(vec![0, 3]));
+        assert_eq!(Count::default().to_string(), "0");
+        // 2^128, which takes three chunks of nineteen digits, one of them all zeros past its first.
+        assert_eq!(
+            Count::from_limbs(vec![0, 0, 1]).to_string(),
+            "340282366920938463463374607431768211456"
+        );
+        assert_eq!(
+            Count::from(10_000_000_000_000_000_000).to_string(),
+            "10000000000000000000"
+        );
+    }
+
+    #[test]
+    fn counts_past_u128() {
+        // 95^20 printable ASCII strings of length 20.
+        let mut language = language("[ -~]*");
+        assert_eq!(
+            language.count_of_length(20).to_string(),
+            "3584859224085422343574104404449462890625"
+        );
+    }
+
+    #[test]
+    fn finite_language() {
+        let mut language = language("ab|cd?");
+        assert!(!language.is_empty());
+        assert!(language.is_finite());
+        assert_eq!(language.longest_length(), Some(2));
+        assert_eq!(language.counts_up_to(3), counts(&[0, 1, 2, 0]));
+        assert_eq!(language.count(), Some(Count::from(3)));
+    }
+
+    #[test]
+    fn infinite_language() {
+        let mut language = language("(a|b)*abb");
+        assert!(!language.is_finite());
+        assert_eq!(language.longest_length(), None);
+        assert_eq!(language.count(), None);
+        assert_eq!(language.count_of_length(4), Count::from(2));
+    }
+
+    #[test]
+    fn empty_language() {
+        let mut language = language("a&b");
+        assert!(language.is_empty());
+        assert!(language.is_finite());
+        assert_eq!(language.count(), Some(Count::default()));
+        assert_eq!(language.dfa().shortest_length(), None);
+    }
+
+    #[test]
+    fn questions_on_the_automaton() {
+        let fa = parse_to_nfa("ab|cd?").unwrap();
+        assert!(fa.is_finite());
+        assert_eq!(fa.longest_length(), Some(2));
+        assert_eq!(fa.count_of_length(2), Count::from(2));
+        assert_eq!(fa.count(), Some(Count::from(3)));
+        let fa = parse_to_nfa("(a|b)*abb").unwrap();
+        assert!(!fa.is_finite());
+        assert_eq!(fa.longest_length(), None);
+        assert_eq!(fa.count(), None);
+    }
+}
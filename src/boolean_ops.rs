@@ -0,0 +1,220 @@
+// Boolean operations on the languages of finite automata: intersection, union and difference through the product
+// construction, and complement relative to an explicit alphabet.
+
+use crate::fa::{State, FA};
+use crate::symbol::Symbol;
+use crate::transition::Transition;
+
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ProductOp {
+    Intersection,
+    Union,
+    Difference,
+}
+
+// A state of the product automaton.  `None` stands for the implicit dead state of a DFA with missing transitions.
+pub type Pair = (Option<State>, Option<State>);
+
+pub fn step(fa: &FA, state: Option<State>, c: char) -> Option<State> {
+    state.and_then(|s| fa.next_state(s, c))
+}
+
+pub fn accepts(fa: &FA, state: Option<State>) -> bool {
+    state.is_some_and(|s| fa.is_accepting(s))
+}
+
+impl ProductOp {
+    fn accepts(self, left: bool, right: bool) -> bool {
+        match self {
+            ProductOp::Intersection => left && right,
+            ProductOp::Union => left || right,
+            ProductOp::Difference => left && !right,
+        }
+    }
+    // Whether a pair can still lead to acceptance.  Pairs that cannot are left out of the product.
+    fn live(self, (left, right): Pair) -> bool {
+        match self {
+            ProductOp::Intersection => left.is_some() && right.is_some(),
+            ProductOp::Union => left.is_some() || right.is_some(),
+            ProductOp::Difference => left.is_some(),
+        }
+    }
+}
+
+impl FA {
+    // Runs both automata in lockstep.  Each state of the result is a pair of states, one from each determinized
+    // operand, and `op` decides which pairs accept.  States are numbered breadth-first from 0, the starting pair.
+    pub fn product(&self, other: &FA, op: ProductOp) -> FA {
+        let left = self.to_dfa();
+        let right = other.to_dfa();
+
+        let mut alphabet = left.alphabet();
+        alphabet.extend(right.alphabet());
+        alphabet.sort_unstable();
+        alphabet.dedup();
+
+        let mut fa = FA::new();
+        let start: Pair = (Some(left.starting()), Some(right.starting()));
+        let mut number: HashMap<Pair, State> = HashMap::new();
+        number.insert(start, 0);
+        fa.add_state(0);
+        let mut todo = VecDeque::new();
+        todo.push_back(start);
+
+        while let Some(pair) = todo.pop_front() {
+            let id = number[&pair];
+            if op.accepts(accepts(&left, pair.0), accepts(&right, pair.1)) {
+                fa.add_acceptor(id);
+            }
+            for &c in &alphabet {
+                let next = (step(&left, pair.0, c), step(&right, pair.1, c));
+                if !op.live(next) {
+                    continue;
+                }
+                let next_id = match number.get(&next) {
+                    Some(&next_id) => next_id,
+                    None => {
+                        let next_id = number.len();
+                        number.insert(next, next_id);
+                        fa.add_state(next_id);
+                        todo.push_back(next);
+                        next_id
+                    }
+                };
+                fa.add_transition(Transition::from(Symbol::Char(c), id, next_id));
+            }
+        }
+
+        fa.set_start(0);
+        fa
+    }
+    pub fn intersection(&self, other: &FA) -> FA {
+        self.product(other, ProductOp::Intersection)
+    }
+    // A DFA for the strings of either automaton.  `union` from the regular operations accepts the same strings, but
+    // joins the two automata into an NFA instead.
+    pub fn union_product(&self, other: &FA) -> FA {
+        self.product(other, ProductOp::Union)
+    }
+    pub fn difference(&self, other: &FA) -> FA {
+        self.product(other, ProductOp::Difference)
+    }
+    // Determinizes this automaton and gives every reachable state a transition on every symbol of `alphabet`, adding
+    // a dead state as the target of the missing ones.  Transitions on symbols outside `alphabet` are dropped.
+    pub fn complete(&self, alphabet: &[char]) -> FA {
+        let dfa = self.to_dfa();
+        let states = dfa.reachable_states();
+        let dead = states.iter().max().map_or(0, |&max| max + 1);
+
+        let mut fa = FA::new();
+        let mut needs_dead = false;
+        for &s in &states {
+            fa.add_state(s);
+            if dfa.is_accepting(s) {
+                fa.add_acceptor(s);
+            }
+            for &c in alphabet {
+                let next = dfa.next_state(s, c).unwrap_or_else(|| {
+                    needs_dead = true;
+                    dead
+                });
+                fa.add_transition(Transition::from(Symbol::Char(c), s, next));
+            }
+        }
+        if needs_dead {
+            fa.add_state(dead);
+            for &c in alphabet {
+                fa.add_transition(Transition::from(Symbol::Char(c), dead, dead));
+            }
+        }
+
+        fa.set_start(dfa.starting());
+        fa
+    }
+    // Whether every string this automaton accepts is also accepted by `other`.
+    pub fn is_subset_of(&self, other: &FA) -> bool {
+        self.difference(other).shortest_accepted().is_none()
+    }
+    // The strings over `alphabet` that this automaton rejects.
+    pub fn complement(&self, alphabet: &[char]) -> FA {
+        let mut fa = self.complete(alphabet);
+        let rejecting = fa
+            .states()
+            .iter()
+            .filter(|&&s| !fa.is_accepting(s))
+            .copied()
+            .collect();
+        *fa.accepting_mut() = rejecting;
+        fa
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivalence::{equivalence, Equivalence};
+    use crate::regex_parser::parse_to_nfa;
+
+    fn fa(regex: &str) -> FA {
+        parse_to_nfa(regex).unwrap()
+    }
+
+    #[test]
+    fn intersection() {
+        let both = fa("(a|b)*a").intersection(&fa("(a|b)*b(a|b)*"));
+        assert!(both.is_deterministic());
+        assert_eq!(
+            equivalence(&both, &fa("(a|b)*b(a|b)*a")),
+            Equivalence::Equivalent
+        );
+    }
+
+    #[test]
+    fn union_product() {
+        let either = fa("a*").union_product(&fa("b*"));
+        assert!(either.is_deterministic());
+        assert_eq!(equivalence(&either, &fa("a*|b*")), Equivalence::Equivalent);
+        assert_eq!(
+            equivalence(&either, &fa("a*").union(&fa("b*"))),
+            Equivalence::Equivalent
+        );
+    }
+
+    #[test]
+    fn difference() {
+        let rest = fa("(a|b)*").difference(&fa("(a|b)*a"));
+        assert_eq!(
+            equivalence(&rest, &fa("((a|b)*b)?")),
+            Equivalence::Equivalent
+        );
+        assert!(fa("(a|b)*a")
+            .difference(&fa("(a|b)*"))
+            .shortest_accepted()
+            .is_none());
+    }
+
+    #[test]
+    fn complement_of_incomplete_dfa() {
+        // The DFA for `ab` has no transitions on `b` from its start, or on anything from its accepting state.
+        let dfa = fa("ab").dfa_from();
+        let complement = dfa.complement(&['a', 'b']);
+        assert!(complement.is_deterministic());
+        for &s in complement.states() {
+            assert_eq!(complement.transitions_of(s).map_or(0, Vec::len), 2);
+        }
+        for accepted in ["", "a", "b", "aa", "ba", "abb", "aba"] {
+            assert!(
+                complement.dfa_accepts(accepted.to_string()),
+                "{:?}",
+                accepted
+            );
+        }
+        assert!(!complement.dfa_accepts("ab".to_string()));
+        assert_eq!(
+            equivalence(&complement.complement(&['a', 'b']), &dfa),
+            Equivalence::Equivalent
+        );
+    }
+}
@@ -0,0 +1,214 @@
+// Submatch extraction: the span of the input each group of a regex matched.  The regex is built into a Thompson NFA
+// with a state to open and a state to close each group, and the NFA is simulated as a Pike VM does it: a list of
+// threads in priority order, each carrying the positions where it last opened and closed every group.  Reaching a
+// group's opening or closing state writes the current position into the thread's slot for it.
+//
+// As in `search`, a state's transitions are tried in the order they were added, so the match found is the
+// leftmost-first one, with the left side of `|` preferred and the repetitions greedy.  Group 0 is the whole match.
+
+use crate::fa::{State, FA};
+use crate::symbol::Symbol;
+use crate::thompsons::{self, Expr, GroupStates};
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+// The start and end of each group, one pair of slots per group.
+type Slots = Vec<Option<usize>>;
+
+#[derive(Debug, Clone)]
+pub struct PikeVM {
+    nfa: FA,
+    groups: Vec<GroupStates>,
+    // The slot each opening or closing state writes to.
+    slot_of: HashMap<State, usize>,
+}
+
+impl PikeVM {
+    // Fails if a group is inside `&` or `~`, where it cannot capture.
+    pub fn new(expr: &Expr) -> Result<Self, String> {
+        let (nfa, groups) = thompsons::expr_to_tagged_nfa(expr.clone())?;
+        let mut slot_of = HashMap::new();
+        for (i, group) in groups.iter().enumerate() {
+            slot_of.insert(group.open, 2 * (i + 1));
+            slot_of.insert(group.close, 2 * (i + 1) + 1);
+        }
+        Ok(Self {
+            nfa,
+            groups,
+            slot_of,
+        })
+    }
+    // The number of groups, counting group 0.
+    pub fn group_count(&self) -> usize {
+        self.groups.len() + 1
+    }
+    // The leftmost-first match in the haystack, with the spans of its groups.
+    pub fn captures<'r>(&'r self, haystack: &str) -> Option<Captures<'r>> {
+        let mut threads = Vec::new();
+        let mut seen = HashSet::new();
+        let mut found = None;
+        let mut at = 0;
+
+        loop {
+            // Until something matches, a match may start here, with less priority than the ones that started
+            // further left.
+            if found.is_none() {
+                let mut slots = vec![None; 2 * self.group_count()];
+                slots[0] = Some(at);
+                self.add_thread(&mut threads, &mut seen, self.nfa.starting(), slots, at);
+            }
+            let c = haystack[at..].chars().next();
+            let mut next = Vec::new();
+            let mut next_seen = HashSet::new();
+            for (state, slots) in threads {
+                // The threads after one that accepts have less priority than its match, so they are dropped.
+                if self.nfa.is_accepting(state) {
+                    let mut slots = slots;
+                    slots[1] = Some(at);
+                    found = Some(slots);
+                    break;
+                }
+                let c = match c {
+                    Some(c) => c,
+                    None => continue,
+                };
+                for t in self.nfa.transitions_of(state).into_iter().flatten() {
+                    if t.sym() == Symbol::Char(c) {
+                        let end = at + c.len_utf8();
+                        self.add_thread(&mut next, &mut next_seen, t.end(), slots.clone(), end);
+                    }
+                }
+            }
+            threads = next;
+            seen = next_seen;
+            match c {
+                Some(c) if !(threads.is_empty() && found.is_some()) => at += c.len_utf8(),
+                _ => break,
+            }
+        }
+
+        found.map(|slots| Captures { vm: self, slots })
+    }
+    // Adds a thread at a state and everything its empty transitions lead to, depth first in the order of the
+    // transitions, recording the groups opened and closed on the way.  A state already on the list keeps its earlier,
+    // higher priority thread.
+    fn add_thread(
+        &self,
+        threads: &mut Vec<(State, Slots)>,
+        seen: &mut HashSet<State>,
+        state: State,
+        mut slots: Slots,
+        at: usize,
+    ) {
+        if !seen.insert(state) {
+            return;
+        }
+        if let Some(&slot) = self.slot_of.get(&state) {
+            slots[slot] = Some(at);
+        }
+        threads.push((state, slots.clone()));
+        for t in self.nfa.transitions_of(state).into_iter().flatten() {
+            if t.sym() == Symbol::Empty {
+                self.add_thread(threads, seen, t.end(), slots.clone(), at);
+            }
+        }
+    }
+}
+
+// The groups of one match, as ranges of byte offsets.
+#[derive(Debug, Clone)]
+pub struct Captures<'r> {
+    vm: &'r PikeVM,
+    slots: Slots,
+}
+
+impl Captures<'_> {
+    // The span of group `i`, if it took part in the match.
+    pub fn get(&self, i: usize) -> Option<Range<usize>> {
+        match (self.slots.get(2 * i)?, self.slots.get(2 * i + 1)?) {
+            (Some(start), Some(end)) => Some(*start..*end),
+            _ => None,
+        }
+    }
+    // The span of the group with this name, if it took part in the match.
+    pub fn name(&self, name: &str) -> Option<Range<usize>> {
+        let i = self
+            .vm
+            .groups
+            .iter()
+            .position(|group| group.name.as_deref() == Some(name))?;
+        self.get(i + 1)
+    }
+    // Each group's name, if it has one, and span, starting from group 0.
+    pub fn iter(&self) -> impl Iterator<Item = (Option<&str>, Option<Range<usize>>)> + '_ {
+        let names =
+            std::iter::once(None).chain(self.vm.groups.iter().map(|group| group.name.as_deref()));
+        names.enumerate().map(move |(i, name)| (name, self.get(i)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex_parser::parse_to_expr;
+
+    fn vm(regex: &str) -> PikeVM {
+        PikeVM::new(&parse_to_expr(regex).unwrap()).unwrap()
+    }
+
+    fn spans(regex: &str, haystack: &str) -> Option<Vec<Option<Range<usize>>>> {
+        let vm = vm(regex);
+        let caps = vm.captures(haystack)?;
+        Some(caps.iter().map(|(_, span)| span).collect())
+    }
+
+    #[test]
+    fn numbered_groups() {
+        assert_eq!(
+            spans("(a+)(b+)", "xaabbb"),
+            Some(vec![Some(1..6), Some(1..3), Some(3..6)])
+        );
+        assert_eq!(
+            spans("((a)b)c", "abc"),
+            Some(vec![Some(0..3), Some(0..2), Some(0..1)])
+        );
+    }
+
+    #[test]
+    fn named_groups() {
+        let vm = vm("v(?<major>[0-9]+)_(?<minor>[0-9]+)");
+        let caps = vm.captures("release v12_3").unwrap();
+        assert_eq!(caps.name("major"), Some(9..11));
+        assert_eq!(caps.name("minor"), Some(12..13));
+        assert_eq!(caps.name("patch"), None);
+        let names: Vec<_> = caps.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec![None, Some("major"), Some("minor")]);
+    }
+
+    #[test]
+    fn repeated_group_keeps_its_last_iteration() {
+        assert_eq!(spans("(a|b)+", "abb"), Some(vec![Some(0..3), Some(2..3)]));
+        assert_eq!(
+            spans("((a)|b)*", "ab"),
+            Some(vec![Some(0..2), Some(1..2), Some(0..1)])
+        );
+    }
+
+    #[test]
+    fn groups_outside_the_match_are_unset() {
+        assert_eq!(
+            spans("(a)|(b)", "b"),
+            Some(vec![Some(0..1), None, Some(0..1)])
+        );
+        assert_eq!(spans("a()b", "ab"), Some(vec![Some(0..2), Some(1..1)]));
+        assert_eq!(spans("(a)", "b"), None);
+    }
+
+    #[test]
+    fn groups_inside_intersection_or_complement_are_rejected() {
+        assert!(PikeVM::new(&parse_to_expr("(a)&a").unwrap()).is_err());
+        assert!(PikeVM::new(&parse_to_expr("~(a*)").unwrap()).is_err());
+        assert!(PikeVM::new(&parse_to_expr("(~a)b").unwrap()).is_ok());
+    }
+}
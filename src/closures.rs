@@ -0,0 +1,110 @@
+// Transformations of the language an automaton accepts: its reversal, and the closures under taking prefixes,
+// suffixes and factors (substrings).  Each result is trimmed, so its starting state is 0.
+
+use crate::fa::FA;
+use crate::symbol::Symbol;
+use crate::transition::Transition;
+
+impl FA {
+    // Accepts the reverse of every string this automaton accepts.  Every transition is flipped, the old starting
+    // state is the one acceptor, and a new starting state has empty transitions to each of the old acceptors.
+    pub fn reverse(&self) -> FA {
+        let start = self.fresh_state();
+        let mut fa = FA::new();
+        fa.add_state(start);
+        fa.set_start(start);
+        for &s in self.states() {
+            fa.add_state(s);
+        }
+        fa.add_acceptor(self.starting());
+        for t in self.delta() {
+            fa.add_transition(Transition::from(t.sym(), t.end(), t.start()));
+        }
+        for &a in self.accepting() {
+            fa.add_transition(Transition::from(Symbol::Empty, start, a));
+        }
+        fa.trim();
+        fa
+    }
+    // Accepts every prefix of a string this automaton accepts.  Once trimmed, every state can still lead to
+    // acceptance, so every state accepts.
+    pub fn prefixes(&self) -> FA {
+        let mut fa = self.clone();
+        fa.trim();
+        // A trimmed automaton that accepts nothing has no acceptors left, and must keep it that way.
+        if !fa.accepting().is_empty() {
+            for s in fa.states().clone() {
+                if !fa.is_accepting(s) {
+                    fa.add_acceptor(s);
+                }
+            }
+        }
+        fa
+    }
+    // Accepts every suffix of a string this automaton accepts, by starting anywhere: a new starting state has empty
+    // transitions to every state of the trimmed automaton.
+    pub fn suffixes(&self) -> FA {
+        let mut fa = self.clone();
+        fa.trim();
+        if fa.accepting().is_empty() {
+            return fa;
+        }
+        let start = fa.fresh_state();
+        fa.add_state(start);
+        for s in fa.states().clone() {
+            if s != start {
+                fa.add_transition(Transition::from(Symbol::Empty, start, s));
+            }
+        }
+        fa.set_start(start);
+        fa.trim();
+        fa
+    }
+    // Accepts every substring of a string this automaton accepts: the suffixes of its prefixes.
+    pub fn factors(&self) -> FA {
+        self.prefixes().suffixes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::equivalence::{equivalence, Equivalence};
+    use crate::regex_parser::parse_to_nfa;
+
+    fn same(fa: &crate::fa::FA, regex: &str) {
+        let expected = parse_to_nfa(regex).unwrap();
+        assert_eq!(
+            equivalence(fa, &expected),
+            Equivalence::Equivalent,
+            "{}",
+            regex
+        );
+    }
+
+    #[test]
+    fn reverse() {
+        same(&parse_to_nfa("ab*c").unwrap().reverse(), "cb*a");
+        same(&parse_to_nfa("(a|b)*abb").unwrap().reverse(), "bba(a|b)*");
+        same(&parse_to_nfa("a&b").unwrap().reverse(), "a&b");
+    }
+
+    #[test]
+    fn prefixes() {
+        same(&parse_to_nfa("abc").unwrap().prefixes(), "ε|a|ab|abc");
+        same(&parse_to_nfa("(ab)*").unwrap().prefixes(), "(ab)*a?");
+    }
+
+    #[test]
+    fn suffixes() {
+        same(&parse_to_nfa("abc").unwrap().suffixes(), "ε|c|bc|abc");
+        same(&parse_to_nfa("a*b").unwrap().suffixes(), "a*b|ε");
+    }
+
+    #[test]
+    fn factors() {
+        same(&parse_to_nfa("abc").unwrap().factors(), "ε|a|b|c|ab|bc|abc");
+        same(&parse_to_nfa("xa*y").unwrap().factors(), "x?a*y?");
+        // The empty language has no factors, not even the empty string.
+        same(&parse_to_nfa("a&b").unwrap().factors(), "a&b");
+    }
+}
@@ -0,0 +1,352 @@
+// Generates test inputs that exercise every reachable transition and accepting state of an automaton, written out
+// one per line as golden expectations:
+//
+// accept "ab"
+// reject "a"
+
+use crate::fa::{State, FA};
+use crate::symbol::Symbol;
+use crate::transition::Transition;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Expectation {
+    pub input: String,
+    pub accept: bool,
+}
+
+// A run through the automaton, as the transitions it takes in order.
+type Path = Vec<Transition>;
+
+// Strings are built from shortest paths: the shortest way into a transition's source, the transition itself, then
+// the shortest way from its target to acceptance.  Each string covers every transition its path goes through, so a
+// transition only gets its own string if no earlier one went through it.  A transition into a state that cannot lead
+// to acceptance is exercised by a string that stops right after it.  These are followed by the strings that probe
+// rejection: each non-accepting state's shortest access string, and from each state one symbol it has no transition
+// on.  Empty transitions are free steps, and every string's verdict is the one the automaton gives it, since in an
+// NFA another run may decide it.
+pub fn suite(fa: &FA) -> Vec<Expectation> {
+    let dfa = fa.to_dfa();
+    let alphabet = fa.alphabet();
+
+    let (access, order) = shortest_access(fa);
+    let finish = shortest_finish(fa);
+
+    let mut expectations = Vec::new();
+    let mut seen = HashSet::new();
+    let mut covered: HashSet<Transition> = HashSet::new();
+    let mut ended: HashSet<State> = HashSet::new();
+    let mut add = |input: String, expectations: &mut Vec<Expectation>| {
+        if seen.insert(input.clone()) {
+            let accept = dfa.dfa_accepts(input.clone());
+            expectations.push(Expectation { input, accept });
+        }
+    };
+
+    for &p in &order {
+        for t in outgoing(fa, p) {
+            if covered.contains(&t) {
+                continue;
+            }
+            let mut path = access[&p].clone();
+            path.push(t);
+            if let Some(rest) = finish.get(&t.end()) {
+                path.extend(rest);
+            }
+            covered.extend(path.iter().copied());
+            ended.extend(path.last().map(|t| t.end()));
+            add(spell(&path), &mut expectations);
+        }
+    }
+    for &q in &order {
+        if fa.is_accepting(q) && !ended.contains(&q) {
+            add(spell(&access[&q]), &mut expectations);
+        }
+    }
+
+    for &q in &order {
+        if !fa.is_accepting(q) {
+            add(spell(&access[&q]), &mut expectations);
+        }
+        let missing = alphabet
+            .iter()
+            .find(|&&c| outgoing(fa, q).iter().all(|t| t.sym() != Symbol::Char(c)));
+        if let Some(&c) = missing {
+            add(format!("{}{}", spell(&access[&q]), c), &mut expectations);
+        }
+    }
+
+    expectations
+}
+
+// The characters a path reads.
+fn spell(path: &[Transition]) -> String {
+    path.iter()
+        .filter_map(|t| match t.sym() {
+            Symbol::Char(c) => Some(c),
+            Symbol::Empty => None,
+        })
+        .collect()
+}
+
+// A state's transitions, empty ones first and then by symbol, so the strings found are alphabetically least.
+fn outgoing(fa: &FA, state: State) -> Vec<Transition> {
+    let mut transitions = fa.transitions_of(state).cloned().unwrap_or_default();
+    transitions.sort_by_key(|t| match t.sym() {
+        Symbol::Empty => None,
+        Symbol::Char(c) => Some(c),
+    });
+    transitions
+}
+
+// The shortest path leading to each state, by breadth-first search from the start, and the order states were found.
+// An empty transition goes to the front of the queue, since it adds nothing to the string.
+fn shortest_access(fa: &FA) -> (HashMap<State, Path>, Vec<State>) {
+    let mut access = HashMap::new();
+    let mut order = Vec::new();
+    let mut todo = VecDeque::new();
+    todo.push_back((fa.starting(), Vec::new()));
+
+    while let Some((p, path)) = todo.pop_front() {
+        if access.contains_key(&p) {
+            continue;
+        }
+        for t in outgoing(fa, p) {
+            if access.contains_key(&t.end()) {
+                continue;
+            }
+            let mut longer: Path = path.clone();
+            longer.push(t);
+            match t.sym() {
+                Symbol::Empty => todo.push_front((t.end(), longer)),
+                Symbol::Char(_) => todo.push_back((t.end(), longer)),
+            }
+        }
+        order.push(p);
+        access.insert(p, path);
+    }
+    (access, order)
+}
+
+// The shortest path leading from each state to acceptance, by breadth-first search backwards from the acceptors.
+fn shortest_finish(fa: &FA) -> HashMap<State, Path> {
+    let mut incoming: HashMap<State, Vec<Transition>> = HashMap::new();
+    for &t in fa.delta() {
+        incoming.entry(t.end()).or_default().push(t);
+    }
+
+    let mut finish = HashMap::new();
+    let mut todo = VecDeque::new();
+    for &a in fa.accepting() {
+        todo.push_back((a, Vec::new()));
+    }
+    while let Some((q, path)) = todo.pop_front() {
+        if finish.contains_key(&q) {
+            continue;
+        }
+        for &t in incoming.get(&q).into_iter().flatten() {
+            if finish.contains_key(&t.start()) {
+                continue;
+            }
+            let mut longer: Path = vec![t];
+            longer.extend(&path);
+            match t.sym() {
+                Symbol::Empty => todo.push_front((t.start(), longer)),
+                Symbol::Char(_) => todo.push_back((t.start(), longer)),
+            }
+        }
+        finish.insert(q, path);
+    }
+    finish
+}
+
+// Runs each expectation against an automaton, returning the ones it no longer meets.
+pub fn check(fa: &FA, expectations: &[Expectation]) -> Vec<Expectation> {
+    let dfa = fa.to_dfa();
+    expectations
+        .iter()
+        .filter(|e| dfa.dfa_accepts(e.input.clone()) != e.accept)
+        .cloned()
+        .collect()
+}
+
+pub fn write(expectations: &[Expectation]) -> Result<String, std::fmt::Error> {
+    let mut output = String::new();
+    for e in expectations {
+        let verdict = if e.accept { "accept" } else { "reject" };
+        writeln!(output, "{} {:?}", verdict, e.input)?;
+    }
+    Ok(output)
+}
+
+// Reads expectations back from the format `write` produces.  Lines starting with `//` are comments.
+pub fn read(input: &str) -> Result<Vec<Expectation>, Box<dyn std::error::Error>> {
+    let mut expectations = Vec::new();
+
+    for (line_count, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with("//") || line.is_empty() {
+            continue;
+        }
+        let (verdict, quoted) = line.split_once(' ').ok_or_else(|| {
+            format!(
+                "expectation at line {} is missing its input",
+                line_count + 1
+            )
+        })?;
+        let accept = match verdict {
+            "accept" => true,
+            "reject" => false,
+            _ => {
+                return Err(format!(
+                    "expectation at line {} must start with accept or reject",
+                    line_count + 1
+                )
+                .into())
+            }
+        };
+        let input = unquote(quoted.trim()).ok_or_else(|| {
+            format!(
+                "the input at line {} is not a quoted string",
+                line_count + 1
+            )
+        })?;
+        expectations.push(Expectation { input, accept });
+    }
+    Ok(expectations)
+}
+
+// Undoes the escaping `{:?}` applies to a string.
+fn unquote(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut output = String::new();
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => output.push('\n'),
+            't' => output.push('\t'),
+            'r' => output.push('\r'),
+            '0' => output.push('\0'),
+            'u' => {
+                let code = chars
+                    .by_ref()
+                    .skip(1)
+                    .take_while(|&c| c != '}')
+                    .collect::<String>();
+                output.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+            }
+            escaped => output.push(escaped),
+        }
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex_parser::parse_to_nfa;
+
+    // The transitions of a DFA the inputs run through, as far as each one gets.
+    fn exercised(dfa: &FA, expectations: &[Expectation]) -> HashSet<Transition> {
+        let mut exercised = HashSet::new();
+        for e in expectations {
+            let mut state = dfa.starting();
+            for c in e.input.chars() {
+                match dfa.next_state(state, c) {
+                    Some(next) => {
+                        exercised.insert(Transition::from(Symbol::Char(c), state, next));
+                        state = next;
+                    }
+                    None => break,
+                }
+            }
+        }
+        exercised
+    }
+
+    fn reachable_transitions(dfa: &FA) -> HashSet<Transition> {
+        let reachable = dfa.reachable_states();
+        dfa.delta()
+            .iter()
+            .filter(|t| reachable.contains(&t.start()))
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn covers_transitions_minimization_would_merge() {
+        // 2 and 3 are equivalent, so the minimal DFA has one `d` loop where this one has two.
+        let mut fa = FA::new();
+        for s in 0..4 {
+            fa.add_state(s);
+        }
+        for (c, p, q) in [
+            ('a', 0, 1),
+            ('b', 1, 2),
+            ('c', 1, 3),
+            ('d', 2, 2),
+            ('d', 3, 3),
+        ] {
+            fa.add_transition(Transition::from(Symbol::Char(c), p, q));
+        }
+        fa.add_acceptor(2);
+        fa.add_acceptor(3);
+        fa.set_start(0);
+
+        let expectations = suite(&fa);
+        assert_eq!(exercised(&fa, &expectations), reachable_transitions(&fa));
+        assert!(check(&fa, &expectations).is_empty());
+    }
+
+    #[test]
+    fn covers_every_reachable_transition() {
+        let dfa = parse_to_nfa("(a|b)*abb").unwrap().dfa_from();
+        let expectations = suite(&dfa);
+        assert_eq!(exercised(&dfa, &expectations), reachable_transitions(&dfa));
+        assert!(dfa.accepting().iter().all(|&a| {
+            expectations.iter().any(|e| {
+                e.accept
+                    && e.input
+                        .chars()
+                        .try_fold(dfa.starting(), |s, c| dfa.next_state(s, c))
+                        == Some(a)
+            })
+        }));
+        assert!(expectations.iter().any(|e| !e.accept));
+    }
+
+    #[test]
+    fn verdicts_match_an_nfa() {
+        let nfa = parse_to_nfa("if|in|int|else").unwrap();
+        let expectations = suite(&nfa);
+        assert!(check(&nfa, &expectations).is_empty());
+        for keyword in ["if", "in", "int", "else"] {
+            assert!(expectations.contains(&Expectation {
+                input: keyword.to_string(),
+                accept: true,
+            }));
+        }
+    }
+
+    #[test]
+    fn reads_what_it_writes() {
+        let expectations = vec![
+            Expectation {
+                input: "a \"b\"\n".to_string(),
+                accept: true,
+            },
+            Expectation {
+                input: String::new(),
+                accept: false,
+            },
+        ];
+        assert_eq!(read(&write(&expectations).unwrap()).unwrap(), expectations);
+    }
+}
@@ -0,0 +1,306 @@
+// Brzozowski derivatives.  The derivative of a regex by a character `c` matches the rest of every string the regex
+// matches that starts with `c`, so a string is matched when taking the derivative by each of its characters in turn
+// leaves a regex that matches the empty string.
+//
+// Every regex has finitely many derivatives once they are kept in a normal form (alternation is associative,
+// commutative and idempotent, and concatenation is associative), so the distinct derivatives can serve as the states
+// of a DFA.  Intersection and complement need nothing special: their derivatives are the intersection and complement
+// of their operands' derivatives.
+
+use crate::fa::{State, FA};
+use crate::symbol::{Symbol, ASCII};
+use crate::thompsons::Expr;
+use crate::transition::Transition;
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+// A regex in normal form.  The smart constructors below are the only way terms are built, so two terms the
+// normalization considers similar are always equal.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+enum Term {
+    // Matches nothing at all.
+    Nothing,
+    Epsilon,
+    // Any one of these characters, sorted and without repeats.
+    Chars(Vec<char>),
+    // Nested to the right: (rs)t is kept as r(st).
+    Concat(Box<Term>, Box<Term>),
+    Alt(BTreeSet<Term>),
+    Star(Box<Term>),
+    And(BTreeSet<Term>),
+    Not(Box<Term>),
+}
+
+// Matches every string.
+fn everything() -> Term {
+    Term::Not(Box::new(Term::Nothing))
+}
+
+fn concat(r: Term, s: Term) -> Term {
+    match (r, s) {
+        (Term::Nothing, _) | (_, Term::Nothing) => Term::Nothing,
+        (Term::Epsilon, t) | (t, Term::Epsilon) => t,
+        (Term::Concat(r1, r2), s) => concat(*r1, concat(*r2, s)),
+        (r, s) => Term::Concat(Box::new(r), Box::new(s)),
+    }
+}
+
+fn alt(terms: Vec<Term>) -> Term {
+    let mut set = BTreeSet::new();
+    let mut chars = Vec::new();
+    let mut todo = terms;
+    while let Some(t) = todo.pop() {
+        match t {
+            Term::Nothing => {}
+            Term::Alt(inner) => todo.extend(inner),
+            // Characters are gathered into one set, so a|b and [ab] come out the same.
+            Term::Chars(cs) => chars.extend(cs),
+            t => {
+                set.insert(t);
+            }
+        }
+    }
+    if !chars.is_empty() {
+        chars.sort_unstable();
+        chars.dedup();
+        set.insert(Term::Chars(chars));
+    }
+
+    if set.contains(&everything()) {
+        return everything();
+    }
+    match set.len() {
+        0 => Term::Nothing,
+        1 => set.into_iter().next().unwrap(),
+        _ => Term::Alt(set),
+    }
+}
+
+fn and(terms: Vec<Term>) -> Term {
+    let mut set = BTreeSet::new();
+    let mut todo = terms;
+    while let Some(t) = todo.pop() {
+        match t {
+            Term::Nothing => return Term::Nothing,
+            Term::And(inner) => todo.extend(inner),
+            t if t == everything() => {}
+            t => {
+                set.insert(t);
+            }
+        }
+    }
+    match set.len() {
+        0 => everything(),
+        1 => set.into_iter().next().unwrap(),
+        _ => Term::And(set),
+    }
+}
+
+fn star(r: Term) -> Term {
+    match r {
+        Term::Nothing | Term::Epsilon => Term::Epsilon,
+        r @ Term::Star(_) => r,
+        r => Term::Star(Box::new(r)),
+    }
+}
+
+fn not(r: Term) -> Term {
+    match r {
+        Term::Not(r) => *r,
+        r => Term::Not(Box::new(r)),
+    }
+}
+
+fn from_expr(expr: &Expr) -> Term {
+    match expr {
+        Expr::Empty | Expr::Just(Symbol::Empty) => Term::Epsilon,
+        Expr::Just(Symbol::Char(c)) => Term::Chars(vec![*c]),
+        Expr::Class(class) => alt(vec![Term::Chars(class.clone())]),
+        Expr::Or(e1, e2) => alt(vec![from_expr(e1), from_expr(e2)]),
+        Expr::And(e1, e2) => concat(from_expr(e1), from_expr(e2)),
+        Expr::Star(e) => star(from_expr(e)),
+        Expr::Plus(e) => {
+            let t = from_expr(e);
+            concat(t.clone(), star(t))
+        }
+        Expr::QMark(e) => alt(vec![from_expr(e), Term::Epsilon]),
+        Expr::Intersect(e1, e2) => and(vec![from_expr(e1), from_expr(e2)]),
+        Expr::Complement(e) => not(from_expr(e)),
+        Expr::Group(_, e) => from_expr(e),
+    }
+}
+
+fn nullable(t: &Term) -> bool {
+    match t {
+        Term::Nothing | Term::Chars(_) => false,
+        Term::Epsilon | Term::Star(_) => true,
+        Term::Concat(r, s) => nullable(r) && nullable(s),
+        Term::Alt(set) => set.iter().any(nullable),
+        Term::And(set) => set.iter().all(nullable),
+        Term::Not(r) => !nullable(r),
+    }
+}
+
+fn derive(t: &Term, c: char) -> Term {
+    match t {
+        Term::Nothing | Term::Epsilon => Term::Nothing,
+        Term::Chars(cs) => {
+            if cs.binary_search(&c).is_ok() {
+                Term::Epsilon
+            } else {
+                Term::Nothing
+            }
+        }
+        // d(rs) = d(r)s, or d(r)s|d(s) if r can be empty.
+        Term::Concat(r, s) => {
+            let first = concat(derive(r, c), (**s).clone());
+            if nullable(r) {
+                alt(vec![first, derive(s, c)])
+            } else {
+                first
+            }
+        }
+        Term::Alt(set) => alt(set.iter().map(|t| derive(t, c)).collect()),
+        Term::Star(r) => concat(derive(r, c), t.clone()),
+        Term::And(set) => and(set.iter().map(|t| derive(t, c)).collect()),
+        Term::Not(r) => not(derive(r, c)),
+    }
+}
+
+// The characters a term mentions.  Every other character has the same derivative, since the term cannot tell them
+// apart.
+fn mentioned(t: &Term, chars: &mut BTreeSet<char>) {
+    match t {
+        Term::Nothing | Term::Epsilon => {}
+        Term::Chars(cs) => chars.extend(cs),
+        Term::Concat(r, s) => {
+            mentioned(r, chars);
+            mentioned(s, chars);
+        }
+        Term::Alt(set) | Term::And(set) => set.iter().for_each(|t| mentioned(t, chars)),
+        Term::Star(r) | Term::Not(r) => mentioned(r, chars),
+    }
+}
+
+// Whether the expression matches the whole input, by taking one derivative per character.
+pub fn matches(expr: &Expr, input: &str) -> bool {
+    let mut t = from_expr(expr);
+    for c in input.chars() {
+        t = derive(&t, c);
+        if t == Term::Nothing {
+            return false;
+        }
+    }
+    nullable(&t)
+}
+
+// Builds a DFA whose states are the distinct derivatives of the expression, numbered in the order a breadth-first
+// search from the expression itself finds them.  Transitions cover printable ASCII, which complement is taken over;
+// derivatives that match nothing get no state, so a missing transition rejects.
+pub fn dfa_from_expr(expr: &Expr) -> FA {
+    let start = from_expr(expr);
+    let mut chars = BTreeSet::new();
+    mentioned(&start, &mut chars);
+    // The derivative by any character the expression does not mention stands in for all of them.
+    let other = ASCII.iter().copied().find(|c| !chars.contains(c));
+
+    let mut fa = FA::new();
+    let mut ids: HashMap<Term, State> = HashMap::new();
+    let mut todo = VecDeque::new();
+    ids.insert(start.clone(), 0);
+    fa.add_state(0);
+    fa.set_start(0);
+    todo.push_back(start);
+
+    while let Some(t) = todo.pop_front() {
+        let from = ids[&t];
+        if nullable(&t) {
+            fa.add_acceptor(from);
+        }
+        let rest = other.map(|c| derive(&t, c));
+        for &c in ASCII.iter() {
+            let d = if chars.contains(&c) {
+                derive(&t, c)
+            } else {
+                rest.clone().unwrap()
+            };
+            if d == Term::Nothing {
+                continue;
+            }
+            let to = match ids.get(&d) {
+                Some(&to) => to,
+                None => {
+                    let to = ids.len();
+                    ids.insert(d.clone(), to);
+                    fa.add_state(to);
+                    todo.push_back(d);
+                    to
+                }
+            };
+            fa.add_transition(Transition::from(Symbol::Char(c), from, to));
+        }
+    }
+
+    fa
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivalence::{equivalence, Equivalence};
+    use crate::regex_parser::{parse_to_dfa, parse_to_expr};
+
+    const REGEXES: [&str; 8] = [
+        "(a|b)*abb",
+        "a(b|c)+d?",
+        "(ab|a)*b",
+        "[abc]*x|ε",
+        "(a|b)*&~((a|b)*aa(a|b)*)",
+        "~(a*)b",
+        "((a*)*)*",
+        "a&b",
+    ];
+
+    // Against Thompson's construction followed by the subset construction.
+    #[test]
+    fn dfa_agrees_with_thompson() {
+        for regex in REGEXES {
+            let expr = parse_to_expr(regex).unwrap();
+            let dfa = dfa_from_expr(&expr);
+            assert!(dfa.is_deterministic(), "{}", regex);
+            let expected = parse_to_dfa(regex).unwrap();
+            assert_eq!(
+                equivalence(&dfa, &expected),
+                Equivalence::Equivalent,
+                "{}",
+                regex
+            );
+        }
+    }
+
+    #[test]
+    fn matcher_agrees_with_thompson() {
+        for regex in REGEXES {
+            let expr = parse_to_expr(regex).unwrap();
+            let expected = parse_to_dfa(regex).unwrap();
+            for input in [
+                "", "a", "b", "abb", "babb", "abcd", "abab", "aab", "x", "ccx", "aaa", "ab",
+            ] {
+                assert_eq!(
+                    matches(&expr, input),
+                    expected.dfa_accepts(input.to_string()),
+                    "{} on {:?}",
+                    regex,
+                    input
+                );
+            }
+        }
+    }
+
+    // Normalizing the derivatives keeps the DFA as small as the language allows here.
+    #[test]
+    fn derivatives_of_abb() {
+        let dfa = dfa_from_expr(&parse_to_expr("(a|b)*abb").unwrap());
+        assert_eq!(dfa.num_states(), 4);
+    }
+}
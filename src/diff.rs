@@ -0,0 +1,310 @@
+// Structural comparison of two DFAs.  The states of the old and new automaton are matched up by running both from
+// their starting states at once, breadth-first: two states correspond when the same shortest string first reaches
+// both.  Whatever is left unmatched, and whatever differs between matched states, is a difference.  Two automata
+// with no differences are isomorphic: the same up to the numbering of their states.  Only states reachable from the
+// start are compared.
+
+use crate::fa::{State, FA};
+
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Difference {
+    // A state of the old automaton with no counterpart in the new one.
+    Removed(State),
+    // A state of the new automaton with no counterpart in the old one.
+    Added(State),
+    // A matched state that accepts in one automaton and not the other, by its new id.
+    Accepting {
+        state: State,
+        now: bool,
+    },
+    // A transition out of a matched state that goes somewhere else now.  `before` is an old id and `after` a new
+    // one; `None` means there was, or is, no transition on `sym`.
+    Retargeted {
+        state: State,
+        sym: char,
+        before: Option<State>,
+        after: Option<State>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Diff {
+    // Each old state with its new counterpart, in the order they were matched.
+    pub matched: Vec<(State, State)>,
+    pub differences: Vec<Difference>,
+}
+
+impl Diff {
+    pub fn is_isomorphism(&self) -> bool {
+        self.differences.is_empty()
+    }
+    // The new counterpart of an old state.
+    pub fn counterpart(&self, old: State) -> Option<State> {
+        self.matched
+            .iter()
+            .find(|&&(o, _)| o == old)
+            .map(|&(_, n)| n)
+    }
+    // The old state that `new` was matched with.
+    pub fn original(&self, new: State) -> Option<State> {
+        self.matched
+            .iter()
+            .find(|&&(_, n)| n == new)
+            .map(|&(o, _)| o)
+    }
+}
+
+impl FA {
+    // Whether two DFAs are the same up to the numbering of their reachable states.
+    pub fn is_isomorphic_to(&self, other: &FA) -> bool {
+        diff(self, other).is_isomorphism()
+    }
+}
+
+// Compares two DFAs.  Automata with empty or repeated transitions should be determinized first.
+pub fn diff(old: &FA, new: &FA) -> Diff {
+    let mut alphabet = old.alphabet();
+    alphabet.extend(new.alphabet());
+    alphabet.sort_unstable();
+    alphabet.dedup();
+
+    let mut to_new: HashMap<State, State> = HashMap::new();
+    let mut to_old: HashMap<State, State> = HashMap::new();
+    let mut matched = Vec::new();
+    let mut todo = VecDeque::new();
+    to_new.insert(old.starting(), new.starting());
+    to_old.insert(new.starting(), old.starting());
+    matched.push((old.starting(), new.starting()));
+    todo.push_back((old.starting(), new.starting()));
+
+    while let Some((p, q)) = todo.pop_front() {
+        for &c in &alphabet {
+            if let (Some(p), Some(q)) = (old.next_state(p, c), new.next_state(q, c)) {
+                if !to_new.contains_key(&p) && !to_old.contains_key(&q) {
+                    to_new.insert(p, q);
+                    to_old.insert(q, p);
+                    matched.push((p, q));
+                    todo.push_back((p, q));
+                }
+            }
+        }
+    }
+
+    let mut differences = Vec::new();
+    for s in old.reachable_states() {
+        if !to_new.contains_key(&s) {
+            differences.push(Difference::Removed(s));
+        }
+    }
+    for s in new.reachable_states() {
+        if !to_old.contains_key(&s) {
+            differences.push(Difference::Added(s));
+        }
+    }
+    for &(p, q) in &matched {
+        if old.is_accepting(p) != new.is_accepting(q) {
+            differences.push(Difference::Accepting {
+                state: q,
+                now: new.is_accepting(q),
+            });
+        }
+        for &c in &alphabet {
+            let (before, after) = (old.next_state(p, c), new.next_state(q, c));
+            let same = match (before, after) {
+                (Some(b), Some(a)) => to_new.get(&b) == Some(&a),
+                (None, None) => true,
+                _ => false,
+            };
+            if !same {
+                differences.push(Difference::Retargeted {
+                    state: q,
+                    sym: c,
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+
+    Diff {
+        matched,
+        differences,
+    }
+}
+
+// Writes out the differences one per line, new states as `N` and old states as `old N`.
+pub fn describe(diff: &Diff) -> Result<String, std::fmt::Error> {
+    use std::fmt::Write;
+    let mut output = String::new();
+
+    if diff.is_isomorphism() {
+        writeln!(output, "the automata are isomorphic")?;
+    }
+    let renumbered = diff
+        .matched
+        .iter()
+        .filter(|(o, n)| o != n)
+        .map(|(o, n)| format!("old {} is {}", o, n))
+        .collect::<Vec<String>>();
+    if !renumbered.is_empty() {
+        writeln!(output, "renumbered: {}", renumbered.join(", "))?;
+    }
+
+    let target = |state: Option<State>, old: bool| match state {
+        Some(s) if old => format!("went to old {}", s),
+        Some(s) => format!("goes to {}", s),
+        None if old => "had no transition".to_string(),
+        None => "has no transition".to_string(),
+    };
+    for d in &diff.differences {
+        match d {
+            Difference::Removed(s) => writeln!(output, "removed: old {}", s)?,
+            Difference::Added(s) => writeln!(output, "added: {}", s)?,
+            Difference::Accepting { state, now: true } => {
+                writeln!(output, "{} now accepts", state)?
+            }
+            Difference::Accepting { state, now: false } => {
+                writeln!(output, "{} no longer accepts", state)?
+            }
+            Difference::Retargeted {
+                state,
+                sym,
+                before,
+                after,
+            } => writeln!(
+                output,
+                "{} on {:?}: {}, now {}",
+                state,
+                sym,
+                target(*before, true),
+                target(*after, false)
+            )?,
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbol::Symbol;
+    use crate::transition::Transition;
+
+    fn dfa(start: State, accepting: &[State], delta: &[(char, State, State)]) -> FA {
+        let mut fa = FA::new();
+        let mut states = delta
+            .iter()
+            .flat_map(|&(_, p, q)| [p, q])
+            .collect::<Vec<State>>();
+        states.push(start);
+        states.sort_unstable();
+        states.dedup();
+        for s in states {
+            fa.add_state(s);
+        }
+        for &(c, p, q) in delta {
+            fa.add_transition(Transition::from(Symbol::Char(c), p, q));
+        }
+        for &a in accepting {
+            fa.add_acceptor(a);
+        }
+        fa.set_start(start);
+        fa
+    }
+
+    #[test]
+    fn renumbered_automata_are_isomorphic() {
+        let old = dfa(0, &[2], &[('a', 0, 1), ('b', 1, 2), ('a', 2, 1)]);
+        let new = dfa(5, &[3], &[('a', 5, 7), ('b', 7, 3), ('a', 3, 7)]);
+        assert!(old.is_isomorphic_to(&new));
+        assert!(new.is_isomorphic_to(&old));
+        let diff = diff(&old, &new);
+        assert_eq!(diff.counterpart(1), Some(7));
+        assert_eq!(diff.original(3), Some(2));
+        assert_eq!(
+            describe(&diff).unwrap(),
+            "the automata are isomorphic\nrenumbered: old 0 is 5, old 1 is 7, old 2 is 3\n"
+        );
+    }
+
+    #[test]
+    fn added_state() {
+        let old = dfa(0, &[1], &[('a', 0, 1)]);
+        let new = dfa(0, &[1, 2], &[('a', 0, 1), ('b', 1, 2)]);
+        let diff = diff(&old, &new);
+        assert!(!old.is_isomorphic_to(&new));
+        assert_eq!(
+            diff.differences,
+            vec![
+                Difference::Added(2),
+                Difference::Retargeted {
+                    state: 1,
+                    sym: 'b',
+                    before: None,
+                    after: Some(2),
+                },
+            ]
+        );
+        assert_eq!(
+            describe(&diff).unwrap(),
+            "added: 2\n1 on 'b': had no transition, now goes to 2\n"
+        );
+    }
+
+    #[test]
+    fn removed_state() {
+        let old = dfa(0, &[1, 2], &[('a', 0, 1), ('b', 1, 2)]);
+        let new = dfa(0, &[1], &[('a', 0, 1)]);
+        let diff = diff(&old, &new);
+        assert_eq!(diff.differences[0], Difference::Removed(2));
+        assert_eq!(diff.differences.len(), 2);
+        assert!(describe(&diff).unwrap().starts_with("removed: old 2\n"));
+    }
+
+    #[test]
+    fn acceptance_changed() {
+        let old = dfa(0, &[1], &[('a', 0, 1)]);
+        let new = dfa(0, &[0], &[('a', 0, 1)]);
+        let diff = diff(&old, &new);
+        assert_eq!(
+            diff.differences,
+            vec![
+                Difference::Accepting {
+                    state: 0,
+                    now: true
+                },
+                Difference::Accepting {
+                    state: 1,
+                    now: false
+                },
+            ]
+        );
+        assert_eq!(
+            describe(&diff).unwrap(),
+            "0 now accepts\n1 no longer accepts\n"
+        );
+    }
+
+    #[test]
+    fn transition_retargeted() {
+        let old = dfa(0, &[1], &[('a', 0, 1), ('b', 0, 2), ('a', 2, 1)]);
+        let new = dfa(0, &[1], &[('a', 0, 1), ('b', 0, 2), ('a', 2, 2)]);
+        let diff = diff(&old, &new);
+        assert_eq!(
+            diff.differences,
+            vec![Difference::Retargeted {
+                state: 2,
+                sym: 'a',
+                before: Some(1),
+                after: Some(2),
+            }]
+        );
+        assert_eq!(
+            describe(&diff).unwrap(),
+            "2 on 'a': went to old 1, now goes to 2\n"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+// Lists the strings an automaton accepts in shortlex order: shorter strings first, and strings of the same length in
+// alphabetical order.
+
+use crate::fa::{State, StateSet, FA};
+use crate::symbol::Symbol;
+
+use std::collections::{HashMap, HashSet};
+
+// Works on NFAs as well as DFAs, by walking sets of states as subset construction would, but only building the
+// sets it needs.  Each set is cut down to the states that can still reach acceptance.
+pub struct Enumerate<'a> {
+    fa: &'a FA,
+    alphabet: Vec<char>,
+    live: StateSet<State>,
+    max_len: Option<usize>,
+    // The length of the strings currently being listed.
+    length: usize,
+    // The distinct state sets reached by strings of exactly `length` characters.  Once it is empty, every string
+    // has been listed.
+    level: HashSet<StateSet<State>>,
+    // Prefixes still to be extended, with the state set each one leads to.  The alphabetically least is on top.
+    stack: Vec<(String, StateSet<State>)>,
+    // Whether a state set can reach acceptance in exactly the given number of steps.
+    finishes: HashMap<(StateSet<State>, usize), bool>,
+}
+
+impl FA {
+    // Without a `max_len`, the iterator never ends for an infinite language; limit it with `take`.
+    pub fn enumerate(&self, max_len: Option<usize>) -> Enumerate<'_> {
+        let live = self.coaccessible_states();
+        let mut start = self.epsilon_closure(self.starting());
+        start.retain(|s| live.contains(s));
+
+        let mut level = HashSet::new();
+        if !start.is_empty() {
+            level.insert(start);
+        }
+
+        let mut enumerate = Enumerate {
+            fa: self,
+            alphabet: self.alphabet(),
+            live,
+            max_len,
+            length: 0,
+            level,
+            stack: Vec::new(),
+            finishes: HashMap::new(),
+        };
+        enumerate.start_length();
+        enumerate
+    }
+}
+
+impl<'a> Enumerate<'a> {
+    fn step(&self, states: &StateSet<State>, c: char) -> Option<StateSet<State>> {
+        let moved = self.fa.delta_move(states, Symbol::Char(c))?;
+        let mut next = self.fa.epsilon_closure_set(moved);
+        next.retain(|s| self.live.contains(s));
+        if next.is_empty() {
+            None
+        } else {
+            Some(next)
+        }
+    }
+    fn finishes_in(&mut self, states: &StateSet<State>, steps: usize) -> bool {
+        if steps == 0 {
+            return states.iter().any(|&s| self.fa.is_accepting(s));
+        }
+        if let Some(&known) = self.finishes.get(&(states.clone(), steps)) {
+            return known;
+        }
+        let mut result = false;
+        for i in 0..self.alphabet.len() {
+            if let Some(next) = self.step(states, self.alphabet[i]) {
+                if self.finishes_in(&next, steps - 1) {
+                    result = true;
+                    break;
+                }
+            }
+        }
+        self.finishes.insert((states.clone(), steps), result);
+        result
+    }
+    // Seeds the stack with the empty prefix, if any string of the current length is accepted.
+    fn start_length(&mut self) {
+        let mut start = self.fa.epsilon_closure(self.fa.starting());
+        start.retain(|s| self.live.contains(s));
+        if !start.is_empty() && self.finishes_in(&start, self.length) {
+            self.stack.push((String::new(), start));
+        }
+    }
+    // Moves on to the next length, returning false when there are no more strings to list.
+    fn next_length(&mut self) -> bool {
+        let mut level = HashSet::new();
+        for states in &self.level {
+            for &c in &self.alphabet {
+                if let Some(next) = self.step(states, c) {
+                    level.insert(next);
+                }
+            }
+        }
+        self.level = level;
+        self.length += 1;
+
+        if self.level.is_empty() || self.max_len.is_some_and(|max| self.length > max) {
+            return false;
+        }
+        self.start_length();
+        true
+    }
+}
+
+impl<'a> Iterator for Enumerate<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            while let Some((prefix, states)) = self.stack.pop() {
+                let remaining = self.length - prefix.chars().count();
+                if remaining == 0 {
+                    return Some(prefix);
+                }
+                // Push in reverse, so the alphabetically least extension comes off the stack first.
+                for i in (0..self.alphabet.len()).rev() {
+                    let c = self.alphabet[i];
+                    if let Some(next) = self.step(&states, c) {
+                        if self.finishes_in(&next, remaining - 1) {
+                            let mut extended = prefix.clone();
+                            extended.push(c);
+                            self.stack.push((extended, next));
+                        }
+                    }
+                }
+            }
+            if self.level.is_empty() || !self.next_length() {
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex_parser::parse_to_nfa;
+
+    fn strings(regex: &str, max_len: Option<usize>) -> Vec<String> {
+        parse_to_nfa(regex)
+            .unwrap()
+            .enumerate(max_len)
+            .take(100)
+            .collect()
+    }
+
+    #[test]
+    fn shortlex_order_up_to_a_length() {
+        assert_eq!(
+            strings("(a|b)*c", Some(3)),
+            vec!["c", "ac", "bc", "aac", "abc", "bac", "bbc"]
+        );
+        assert_eq!(strings("(a|b)*c", Some(0)), Vec::<String>::new());
+    }
+
+    #[test]
+    fn infinite_language_without_a_bound() {
+        let first = parse_to_nfa("(b|a)*c")
+            .unwrap()
+            .enumerate(None)
+            .take(4)
+            .collect::<Vec<String>>();
+        assert_eq!(first, vec!["c", "ac", "bc", "aac"]);
+    }
+
+    #[test]
+    fn empty_string_comes_first() {
+        assert_eq!(strings("a?b?", None), vec!["", "a", "b", "ab"]);
+    }
+
+    #[test]
+    fn empty_language_gives_no_strings() {
+        assert!(strings("a&b", None).is_empty());
+        assert!(strings("~(a*)&a", Some(5)).is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+// Decides whether two finite automata accept the same language.
+
+use crate::boolean_ops::{accepts, step, Pair};
+use crate::fa::FA;
+
+use std::collections::{HashMap, VecDeque};
+
+// One side of a comparison: the first automaton given, or the second.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Equivalence {
+    Equivalent,
+    // `witness` is a shortest string that only one of the automata accepts; `accepted_by` says which one.
+    Distinct { witness: String, accepted_by: Side },
+}
+
+// Walks the product of the two DFAs breadth-first, looking for a pair of states where exactly one side accepts.
+// Symbols are tried in sorted order, so the first such pair found is reached by the shortest, and then
+// alphabetically least, distinguishing string.
+pub fn equivalence(left: &FA, right: &FA) -> Equivalence {
+    let left = left.to_dfa();
+    let right = right.to_dfa();
+
+    let mut alphabet = left.alphabet();
+    alphabet.extend(right.alphabet());
+    alphabet.sort_unstable();
+    alphabet.dedup();
+
+    let start: Pair = (Some(left.starting()), Some(right.starting()));
+    // Each visited pair remembers the pair and symbol it was first reached from, so the witness can be read back.
+    let mut parent: HashMap<Pair, Option<(Pair, char)>> = HashMap::new();
+    parent.insert(start, None);
+    let mut todo = VecDeque::new();
+    todo.push_back(start);
+
+    while let Some(pair) = todo.pop_front() {
+        let (l, r) = pair;
+        let (l_accepts, r_accepts) = (accepts(&left, l), accepts(&right, r));
+        if l_accepts != r_accepts {
+            let mut witness = Vec::new();
+            let mut cur = pair;
+            while let Some(&Some((prev, c))) = parent.get(&cur) {
+                witness.push(c);
+                cur = prev;
+            }
+            return Equivalence::Distinct {
+                witness: witness.into_iter().rev().collect(),
+                accepted_by: if l_accepts { Side::Left } else { Side::Right },
+            };
+        }
+
+        for &c in &alphabet {
+            let next = (step(&left, l, c), step(&right, r, c));
+            // Both sides dead: nothing past here can be accepted by either.
+            if next == (None, None) || parent.contains_key(&next) {
+                continue;
+            }
+            parent.insert(next, Some((pair, c)));
+            todo.push_back(next);
+        }
+    }
+
+    Equivalence::Equivalent
+}
+
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+impl std::fmt::Display for Equivalence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Equivalence::Equivalent => write!(f, "equivalent"),
+            Equivalence::Distinct {
+                witness,
+                accepted_by,
+            } => write!(
+                f,
+                "not equivalent: {:?} is accepted by the {} automaton only",
+                witness, accepted_by
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex_parser::parse_to_nfa;
+
+    fn compare(left: &str, right: &str) -> Equivalence {
+        equivalence(&parse_to_nfa(left).unwrap(), &parse_to_nfa(right).unwrap())
+    }
+
+    fn distinct(witness: &str, accepted_by: Side) -> Equivalence {
+        Equivalence::Distinct {
+            witness: witness.to_string(),
+            accepted_by,
+        }
+    }
+
+    #[test]
+    fn equivalent_pairs() {
+        assert_eq!(compare("(a|b)*", "(a*b*)*"), Equivalence::Equivalent);
+        assert_eq!(compare("a(ba)*", "(ab)*a"), Equivalence::Equivalent);
+        assert_eq!(compare("a+|ε", "a*"), Equivalence::Equivalent);
+    }
+
+    #[test]
+    fn shortest_witness() {
+        assert_eq!(compare("a*", "a+"), distinct("", Side::Left));
+        assert_eq!(
+            compare("(a|b)*abb", "(a|b)*bb"),
+            distinct("bb", Side::Right)
+        );
+        assert_eq!(compare("ab|abc", "ab|abcd"), distinct("abc", Side::Left));
+    }
+
+    // A character only one automaton reads is a move to rejection for the other.
+    #[test]
+    fn different_alphabets() {
+        assert_eq!(compare("a*", "a*|b"), distinct("b", Side::Right));
+        assert_eq!(compare("x", "y"), distinct("x", Side::Left));
+        assert_eq!(compare("a*", "a*|(b&c)"), Equivalence::Equivalent);
+    }
+}
@@ -1,313 +1,717 @@
-use crate::fa_reader;
-use crate::symbol::{Symbol, ASCII};
-use crate::transition::{SetTransition, Transition};
-// * Look into using GraphViz to visualize the finite automata, with the 'dot' crate.
-
-use std::collections::BTreeSet;
-use std::collections::HashMap;
-pub type State = usize;
-// A state set should be hashable, so that it can be used as the key to a HashSet or HashMap in subset construction.  Basically, DFA states must be temporarily represented sets with multiple elements; we want to be able to hash them as easily as with NFA states.
-pub type StateSet<T> = BTreeSet<T>;
-
-// The finite automata holds both the mathematical tuple representation and the graph representation, which is really just a table of beginnings of transitions to full transitions.
-// impl Display: uses FA specification syntax
-#[derive(Debug, Clone)]
-pub struct FA {
-    states: Vec<State>,
-    starting: State,
-    accepting: Vec<State>,
-    delta: Vec<Transition>,
-    // * Graph invariant: the state used as key value is the beginning state of its transitions.
-    graph: HashMap<State, Vec<Transition>>,
-}
-
-impl FA {
-    pub fn new() -> Self {
-        Self {
-            states: Vec::new(),
-            starting: 0,
-            accepting: Vec::new(),
-            delta: Vec::new(),
-            graph: HashMap::new(),
-        }
-    }
-    pub fn num_states(&self) -> usize {
-        self.states.len()
-    }
-    pub fn set_start(&mut self, s: State) {
-        self.starting = s;
-    }
-    pub fn add_state(&mut self, s: State) {
-        self.states.push(s);
-        self.graph.insert(s, Vec::new());
-    }
-    pub fn add_acceptor(&mut self, accept: State) {
-        self.accepting.push(accept);
-    }
-    pub fn add_transition(&mut self, t: Transition) {
-        self.delta.push(t);
-        if let Some(v) = self.graph.get_mut(&t.start()) {
-            v.push(t);
-        } else {
-            self.graph.insert(t.start(), vec![t]);
-        }
-    }
-    pub fn is_accepting(&self, id: State) -> bool {
-        self.accepting.contains(&id)
-    }
-    // Does not work on NFAs.  DFAs only.
-    // TODO: FIX THIS - (tt|y*) breaks with ty
-    pub fn dfa_accepts(&self, string: String) -> bool {
-        let mut cur = self.starting;
-
-        for c in string.chars() {
-            // locate a transition with begin==cur and sym==c
-            if let Some(tr) = self.transitions_of(cur) {
-                for t in tr {
-                    if t.sym() == Symbol::Char(c) {
-                        cur = t.end();
-                    }
-                }
-            } else {
-                // if no such transition, return false (don't follow epsilon closure)
-                return false;
-            }
-        }
-
-        return self.accepting.contains(&cur);
-    }
-    pub fn states(&self) -> &Vec<State> {
-        &self.states
-    }
-    pub fn states_mut(&mut self) -> &mut Vec<State> {
-        &mut self.states
-    }
-    pub fn starting(&self) -> State {
-        self.starting
-    }
-    pub fn accepting(&self) -> &Vec<State> {
-        &self.accepting
-    }
-    pub fn accepting_mut(&mut self) -> &mut Vec<State> {
-        &mut self.accepting
-    }
-    pub fn delta(&self) -> &Vec<Transition> {
-        &self.delta
-    }
-    pub fn delta_mut(&mut self) -> &mut Vec<Transition> {
-        &mut self.delta
-    }
-    pub fn transitions_of(&self, id: State) -> Option<&Vec<Transition>> {
-        self.graph.get(&id)
-    }
-    pub fn epsilon_closure(&self, st: State) -> StateSet<State> {
-        // Start with the current node.  A state is always in its own epsilon closure.
-        let mut closure = StateSet::new();
-        closure.insert(st);
-        let mut todo = vec![st];
-
-        while let Some(top) = todo.pop() {
-            if let Some(transitions) = self.transitions_of(top) {
-                for t in transitions {
-                    if t.sym() == Symbol::Empty && t.start() == top {
-                        closure.insert(t.end());
-                        todo.push(t.end());
-                    }
-                }
-            }
-        }
-        closure
-    }
-    // Compute the epsilon-closure of each state in T; return the union of these closures.
-    pub fn epsilon_closure_set(&self, t: StateSet<State>) -> StateSet<State> {
-        let mut res = StateSet::new();
-
-        for states in t.iter().map(|&st| self.epsilon_closure(st)) {
-            for state in states {
-                res.insert(state);
-            }
-        }
-
-        res
-    }
-    // Compute the union of states that can be moved to through the symbol c, from all the states in T.
-    pub fn delta_move(&self, t: &StateSet<State>, c: Symbol) -> Option<StateSet<State>> {
-        let mut res = StateSet::new();
-
-        for state in t {
-            if let Some(states) = self.graph.get(&state) {
-                states
-                    .iter()
-                    .filter(|&t| t.sym() == c)
-                    .map(|t| t.end())
-                    .for_each(|s| {
-                        res.insert(s);
-                    });
-            }
-        }
-        if res.is_empty() {
-            None
-        } else {
-            Some(res)
-        }
-    }
-    // Rather than alter in place, this generates a new finite automata altogether.
-    fn subset_construction(
-        &self,
-    ) -> (
-        HashMap<StateSet<State>, Vec<SetTransition<State>>>,
-        StateSet<State>,
-    ) {
-        let mut dfa: HashMap<StateSet<State>, Vec<SetTransition<State>>> = HashMap::new();
-        let mut todo: Vec<StateSet<State>> = Vec::new();
-
-        // First DFA state: ε-closure of first NFA state.
-        let q0 = self.epsilon_closure(self.starting);
-        dfa.insert(q0.clone(), Vec::new());
-        todo.push(q0.clone());
-
-        while let Some(state_set) = todo.pop() {
-            for c in ASCII {
-                let sym = Symbol::Char(c);
-                if let Some(m) = self.delta_move(&state_set, sym) {
-                    let u = self.epsilon_closure_set(m);
-                    if let Some(v) = dfa.get_mut(&u) {
-                        v.push(SetTransition::from(sym, state_set.clone(), u.clone()));
-                    } else {
-                        // This is the missing link; check if something is in the map and then add to its vec.  Remember, you always want to update the transition table, whether or not the new states were already found.
-                        if let Some(v) = dfa.get_mut(&state_set) {
-                            v.push(SetTransition::from(sym, state_set.clone(), u.clone()));
-                        }
-                        dfa.insert(u.clone(), Vec::new());
-                        todo.push(u);
-                    }
-                }
-                // else, just continue to the next symbol
-            }
-        }
-        (dfa, q0)
-    }
-    pub fn dfa_from(&self) -> Self {
-        let (dfa, start) = self.subset_construction();
-
-        let mut fa = FA::new();
-
-        let mut map = HashMap::new();
-        // let mut acceptors = Vec::new();
-        map.insert(&start, 0);
-        fa.add_state(0);
-        if self.accepting.iter().any(|st| start.contains(st)) {
-            fa.add_acceptor(0);
-        }
-        let mut i = 1usize;
-
-        for state in dfa.keys() {
-            // If any of the NFA states in this DFA state are accepting, the resulting DFA state is accepting.
-            if self.accepting.iter().any(|st| state.contains(st)) {
-                fa.add_acceptor(i);
-            }
-            // If this is the epsilon-closure of the starting NFA state, then this DFA state is accepting.
-            if *state == start {
-                continue;
-            } else {
-                map.insert(state, i);
-                fa.add_state(i);
-                i += 1;
-            }
-        }
-
-        for ts in dfa.values() {
-            for t in ts {
-                let begin = map.get(&t.begin()).unwrap();
-                let end = map.get(&t.end()).unwrap();
-                fa.add_transition(Transition::from(t.sym(), *begin, *end));
-            }
-        }
-
-        fa.set_start(0);
-        // println!("{:?}", fa);
-        fa
-    }
-    pub fn minimize(&mut self) {
-        // Hopcroft's algorithm partitions the states of the DFA so that all states
-        // which have the same response to input are in the same partition.
-
-        // let split = |&s| {
-        //     for c in crate::symbol::ASCII {
-        //         if c
-        //     }
-        // }
-
-        // // vec of statesets
-        // let T = Vec::new();
-        // let P = Vec::new();
-
-        // while P != T {
-
-        // }
-    }
-    // pub fn test(&self) {
-    //     let dfa = self.subset_construction();
-    //     for (stateset, transits) in dfa {
-    //         for t in transits {
-    //             println!("{:?}: {:?} -- {:?} -> {:?}", stateset, t.begin, t.sym, t.end);
-    //         }
-    //     }
-    // }
-}
-
-// A simple pretty printing of a finite automata.
-impl std::fmt::Display for FA {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        use std::fmt::Write;
-        use std::iter::FromIterator;
-        let mut v = Vec::from_iter(self.graph.iter());
-        v.sort_by(|a, b| (a.0).partial_cmp(b.0).unwrap());
-
-        let mut output = String::new();
-        let mut state_count = 0;
-        let mut transition_count = 0;
-
-        for (state, transitions) in v {
-            state_count += 1;
-            writeln!(
-                output,
-                "{} {} {}",
-                state,
-                {
-                    if self.accepting.contains(state) {
-                        fa_reader::ACCEPT_SYMBOL
-                    } else {
-                        fa_reader::STATE_SYMBOL
-                    }
-                },
-                transitions.len()
-            )?;
-
-            for t in transitions {
-                transition_count += 1;
-                writeln!(
-                    output,
-                    "{} -> {}",
-                    {
-                        match t.sym() {
-                            Symbol::Char(c) => c,
-                            Symbol::Empty => ' ',
-                        }
-                    },
-                    t.end()
-                )?;
-            }
-
-            writeln!(output, "")?;
-        }
-
-        writeln!(
-            output,
-            "// {} states, {} transitions",
-            state_count, transition_count
-        )?;
-
-        write!(f, "{}", output)
-    }
-}
+use crate::fa_reader;
+use crate::hopcroft;
+use crate::partition_trace::PartitionTrace;
+use crate::subset_trace::{SubsetMove, SubsetStep, SubsetTrace};
+use crate::symbol::{Symbol, ASCII};
+use crate::transition::{SetTransition, Transition};
+// * Look into using GraphViz to visualize the finite automata, with the 'dot' crate.
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+pub type State = usize;
+// A state set should be hashable, so that it can be used as the key to a HashSet or HashMap in subset construction.  Basically, DFA states must be temporarily represented sets with multiple elements; we want to be able to hash them as easily as with NFA states.
+pub type StateSet<T> = BTreeSet<T>;
+// The DFA built by subset construction, before its state sets are numbered: each set of NFA states maps to the transitions leaving it.
+type SubsetTable = HashMap<StateSet<State>, Vec<SetTransition<State>>>;
+
+// What `FA::refinement_table` gives partition refinement to work on.
+struct RefinementTable {
+    dfa: FA,
+    alphabet: Vec<char>,
+    states: Vec<State>,
+    index: HashMap<State, usize>,
+    table: Vec<Vec<usize>>,
+    accepting: Vec<bool>,
+}
+
+// The finite automata holds both the mathematical tuple representation and the graph representation, which is really just a table of beginnings of transitions to full transitions.
+// impl Display: uses FA specification syntax
+#[derive(Debug, Clone)]
+pub struct FA {
+    states: Vec<State>,
+    starting: State,
+    accepting: Vec<State>,
+    delta: Vec<Transition>,
+    // * Graph invariant: the state used as key value is the beginning state of its transitions.
+    graph: HashMap<State, Vec<Transition>>,
+    // For an automaton made by the subset construction, the set of NFA states each of its states stands for.  Adding
+    // states or transitions afterwards, or changing them in place, clears it, as it would no longer be true.
+    subsets: HashMap<State, StateSet<State>>,
+}
+
+impl FA {
+    pub fn new() -> Self {
+        Self {
+            states: Vec::new(),
+            starting: 0,
+            accepting: Vec::new(),
+            delta: Vec::new(),
+            graph: HashMap::new(),
+            subsets: HashMap::new(),
+        }
+    }
+    pub fn num_states(&self) -> usize {
+        self.states.len()
+    }
+    // A state id that no state or transition uses yet.
+    pub fn fresh_state(&self) -> State {
+        let ends = self.delta.iter().flat_map(|t| [t.start(), t.end()]);
+        self.states
+            .iter()
+            .copied()
+            .chain(ends)
+            .max()
+            .map_or(0, |s| s + 1)
+    }
+    pub fn set_start(&mut self, s: State) {
+        self.starting = s;
+    }
+    pub fn add_state(&mut self, s: State) {
+        self.subsets.clear();
+        self.states.push(s);
+        self.graph.insert(s, Vec::new());
+    }
+    pub fn add_acceptor(&mut self, accept: State) {
+        self.accepting.push(accept);
+    }
+    pub fn add_transition(&mut self, t: Transition) {
+        self.subsets.clear();
+        self.delta.push(t);
+        if let Some(v) = self.graph.get_mut(&t.start()) {
+            v.push(t);
+        } else {
+            self.graph.insert(t.start(), vec![t]);
+        }
+    }
+    pub fn is_accepting(&self, id: State) -> bool {
+        self.accepting.contains(&id)
+    }
+    // Does not work on NFAs.  DFAs only.
+    pub fn dfa_accepts(&self, string: String) -> bool {
+        let mut cur = self.starting;
+
+        for c in string.chars() {
+            // locate a transition with begin==cur and sym==c; if there is none, the string is rejected (don't follow epsilon closure)
+            match self.next_state(cur, c) {
+                Some(next) => cur = next,
+                None => return false,
+            }
+        }
+
+        self.accepting.contains(&cur)
+    }
+    // In a DFA, the state reached by reading `c` from `id`, if there is a transition for it.
+    pub fn next_state(&self, id: State, c: char) -> Option<State> {
+        self.transitions_of(id)?
+            .iter()
+            .find(|t| t.sym() == Symbol::Char(c))
+            .map(|t| t.end())
+    }
+    // The characters that label at least one transition, in sorted order.
+    pub fn alphabet(&self) -> Vec<char> {
+        let mut chars = self
+            .delta
+            .iter()
+            .filter_map(|t| match t.sym() {
+                Symbol::Char(c) => Some(c),
+                Symbol::Empty => None,
+            })
+            .collect::<Vec<char>>();
+        chars.sort_unstable();
+        chars.dedup();
+        chars
+    }
+    // A DFA has no empty transitions and at most one transition per symbol out of each state.
+    pub fn is_deterministic(&self) -> bool {
+        self.graph.values().all(|transitions| {
+            let mut seen = HashSet::new();
+            transitions
+                .iter()
+                .all(|t| t.sym() != Symbol::Empty && seen.insert(t.sym()))
+        })
+    }
+    // Returns this automaton unchanged if it is already a DFA, and its subset construction otherwise.
+    pub fn to_dfa(&self) -> Self {
+        if self.is_deterministic() {
+            self.clone()
+        } else {
+            self.dfa_from()
+        }
+    }
+    pub fn states(&self) -> &Vec<State> {
+        &self.states
+    }
+    pub fn states_mut(&mut self) -> &mut Vec<State> {
+        self.subsets.clear();
+        &mut self.states
+    }
+    pub fn starting(&self) -> State {
+        self.starting
+    }
+    pub fn accepting(&self) -> &Vec<State> {
+        &self.accepting
+    }
+    pub fn accepting_mut(&mut self) -> &mut Vec<State> {
+        &mut self.accepting
+    }
+    pub fn delta(&self) -> &Vec<Transition> {
+        &self.delta
+    }
+    pub fn delta_mut(&mut self) -> &mut Vec<Transition> {
+        self.subsets.clear();
+        &mut self.delta
+    }
+    // The NFA states a state of a determinized automaton stands for.
+    pub fn subset_of(&self, id: State) -> Option<&StateSet<State>> {
+        self.subsets.get(&id)
+    }
+    pub fn transitions_of(&self, id: State) -> Option<&Vec<Transition>> {
+        self.graph.get(&id)
+    }
+    pub fn epsilon_closure(&self, st: State) -> StateSet<State> {
+        // Start with the current node.  A state is always in its own epsilon closure.
+        let mut closure = StateSet::new();
+        closure.insert(st);
+        let mut todo = vec![st];
+
+        while let Some(top) = todo.pop() {
+            if let Some(transitions) = self.transitions_of(top) {
+                for t in transitions {
+                    // Only follow states seen for the first time, or an epsilon cycle would loop forever.
+                    if t.sym() == Symbol::Empty && t.start() == top && closure.insert(t.end()) {
+                        todo.push(t.end());
+                    }
+                }
+            }
+        }
+        closure
+    }
+    // Compute the epsilon-closure of each state in T; return the union of these closures.
+    pub fn epsilon_closure_set(&self, t: StateSet<State>) -> StateSet<State> {
+        let mut res = StateSet::new();
+
+        for states in t.iter().map(|&st| self.epsilon_closure(st)) {
+            for state in states {
+                res.insert(state);
+            }
+        }
+
+        res
+    }
+    // Compute the union of states that can be moved to through the symbol c, from all the states in T.
+    pub fn delta_move(&self, t: &StateSet<State>, c: Symbol) -> Option<StateSet<State>> {
+        let mut res = StateSet::new();
+
+        for state in t {
+            if let Some(states) = self.graph.get(state) {
+                states
+                    .iter()
+                    .filter(|&t| t.sym() == c)
+                    .map(|t| t.end())
+                    .for_each(|s| {
+                        res.insert(s);
+                    });
+            }
+        }
+        if res.is_empty() {
+            None
+        } else {
+            Some(res)
+        }
+    }
+    // Rather than alter in place, this generates a new finite automata altogether.  The state sets are returned in
+    // the order they were found, starting with the ε-closure of the starting state, and each is marked in that
+    // order.  With `trace`, every step is recorded there.
+    fn subset_construction(
+        &self,
+        trace: &mut Option<Vec<SubsetStep>>,
+    ) -> (SubsetTable, Vec<StateSet<State>>) {
+        let mut dfa: SubsetTable = HashMap::new();
+        let mut todo: VecDeque<StateSet<State>> = VecDeque::new();
+        let mut order = Vec::new();
+
+        // First DFA state: ε-closure of first NFA state.
+        let q0 = self.epsilon_closure(self.starting);
+        dfa.insert(q0.clone(), Vec::new());
+        todo.push_back(q0.clone());
+        order.push(q0);
+
+        while let Some(state_set) = todo.pop_front() {
+            let mut moves = Vec::new();
+            for c in ASCII {
+                let sym = Symbol::Char(c);
+                if let Some(m) = self.delta_move(&state_set, sym) {
+                    let u = self.epsilon_closure_set(m.clone());
+                    // Remember, you always want to update the transition table, whether or not the new states were already found.
+                    let new = !dfa.contains_key(&u);
+                    if let Some(v) = dfa.get_mut(&state_set) {
+                        v.push(SetTransition::from(sym, state_set.clone(), u.clone()));
+                    }
+                    if new {
+                        dfa.insert(u.clone(), Vec::new());
+                        todo.push_back(u.clone());
+                        order.push(u.clone());
+                    }
+                    if trace.is_some() {
+                        moves.push(SubsetMove {
+                            sym: c,
+                            moved: m,
+                            closure: u,
+                            new,
+                        });
+                    }
+                }
+                // else, just continue to the next symbol
+            }
+            if let Some(steps) = trace {
+                steps.push(SubsetStep {
+                    set: state_set,
+                    moves,
+                });
+            }
+        }
+        (dfa, order)
+    }
+    // The subset construction, recorded step by step.
+    pub fn subset_trace(&self) -> SubsetTrace {
+        let mut steps = Some(Vec::new());
+        self.subset_construction(&mut steps);
+        SubsetTrace {
+            alphabet: self.alphabet(),
+            accepting: self.accepting.iter().copied().collect(),
+            steps: steps.unwrap_or_default(),
+        }
+    }
+    // The subset construction.  The result keeps the set of NFA states behind each of its states.
+    pub fn dfa_from(&self) -> Self {
+        let (dfa, order) = self.subset_construction(&mut None);
+
+        let mut fa = FA::new();
+
+        let mut map = HashMap::new();
+        // let mut acceptors = Vec::new();
+        // The states are numbered in the order they were found, so the epsilon-closure of the starting NFA state is 0.
+        for (i, state) in order.iter().enumerate() {
+            // If any of the NFA states in this DFA state are accepting, the resulting DFA state is accepting.
+            if self.accepting.iter().any(|st| state.contains(st)) {
+                fa.add_acceptor(i);
+            }
+            map.insert(state, i);
+            fa.add_state(i);
+        }
+
+        for ts in order.iter().map(|state| &dfa[state]) {
+            for t in ts {
+                let begin = map.get(&t.begin()).unwrap();
+                let end = map.get(&t.end()).unwrap();
+                fa.add_transition(Transition::from(t.sym(), *begin, *end));
+            }
+        }
+
+        fa.set_start(0);
+        // println!("{:?}", fa);
+        fa.subsets = map.into_iter().map(|(set, id)| (id, set.clone())).collect();
+        fa
+    }
+    // Every state that can be reached from the starting state.
+    pub fn reachable_states(&self) -> StateSet<State> {
+        let mut seen = StateSet::new();
+        seen.insert(self.starting);
+        let mut todo = vec![self.starting];
+
+        while let Some(top) = todo.pop() {
+            if let Some(transitions) = self.transitions_of(top) {
+                for t in transitions {
+                    if seen.insert(t.end()) {
+                        todo.push(t.end());
+                    }
+                }
+            }
+        }
+        seen
+    }
+    // Every state from which some accepting state can be reached, following transitions backwards from the acceptors.
+    pub fn coaccessible_states(&self) -> StateSet<State> {
+        let mut incoming: HashMap<State, Vec<State>> = HashMap::new();
+        for t in &self.delta {
+            incoming.entry(t.end()).or_default().push(t.start());
+        }
+
+        let mut seen = self.accepting.iter().copied().collect::<StateSet<State>>();
+        let mut todo = seen.iter().copied().collect::<Vec<State>>();
+        while let Some(top) = todo.pop() {
+            for &prev in incoming.get(&top).into_iter().flatten() {
+                if seen.insert(prev) {
+                    todo.push(prev);
+                }
+            }
+        }
+        seen
+    }
+    // An equivalent NFA with no empty transitions.  Each state takes on the symbol transitions and the acceptance of
+    // every state in its epsilon-closure.  States that only empty transitions led to are then unreachable, and left out.
+    // The states that remain keep their numbers, the start included, so it need not be 0; `trim` renumbers from 0.
+    pub fn remove_epsilons(&self) -> Self {
+        let mut fa = FA::new();
+        fa.set_start(self.starting);
+        let mut seen = StateSet::new();
+        seen.insert(self.starting);
+        let mut todo = vec![self.starting];
+
+        while let Some(state) = todo.pop() {
+            fa.add_state(state);
+            let closure = self.epsilon_closure(state);
+            if closure.iter().any(|&s| self.is_accepting(s)) {
+                fa.add_acceptor(state);
+            }
+            let mut lifted = HashSet::new();
+            for t in closure
+                .iter()
+                .filter_map(|&s| self.transitions_of(s))
+                .flatten()
+            {
+                if t.sym() != Symbol::Empty && lifted.insert((t.sym(), t.end())) {
+                    fa.add_transition(Transition::from(t.sym(), state, t.end()));
+                    if seen.insert(t.end()) {
+                        todo.push(t.end());
+                    }
+                }
+            }
+        }
+        fa
+    }
+    // Removes the states that cannot be reached from the start or cannot lead to acceptance, and renumbers the rest
+    // breadth-first from 0, the starting state.  An automaton that accepts nothing is left with only its start.
+    pub fn trim(&mut self) {
+        let reachable = self.reachable_states();
+        let useful = self
+            .coaccessible_states()
+            .into_iter()
+            .filter(|s| reachable.contains(s))
+            .collect::<StateSet<State>>();
+
+        let mut fa = FA::new();
+        fa.add_state(0);
+        fa.set_start(0);
+        let mut number = HashMap::new();
+        number.insert(self.starting, 0);
+        let mut todo = VecDeque::new();
+        if useful.contains(&self.starting) {
+            todo.push_back(self.starting);
+        }
+
+        while let Some(state) = todo.pop_front() {
+            let id = number[&state];
+            if self.is_accepting(state) {
+                fa.add_acceptor(id);
+            }
+            for t in self.transitions_of(state).into_iter().flatten() {
+                if !useful.contains(&t.end()) {
+                    continue;
+                }
+                let next = match number.get(&t.end()) {
+                    Some(&next) => next,
+                    None => {
+                        let next = number.len();
+                        number.insert(t.end(), next);
+                        fa.add_state(next);
+                        todo.push_back(t.end());
+                        next
+                    }
+                };
+                fa.add_transition(Transition::from(t.sym(), id, next));
+            }
+        }
+
+        // The surviving states keep the subsets they stood for under their new numbers.
+        for (state, &id) in &number {
+            if let Some(subset) = self.subsets.get(state) {
+                fa.subsets.insert(id, subset.clone());
+            }
+        }
+        *self = fa;
+    }
+    // The DFA of this automaton as the complete transition table partition refinement works on.  The reachable
+    // states are numbered densely, in the order of `states`, and index `states.len()` is an explicit dead state
+    // standing in for missing transitions.
+    fn refinement_table(&self) -> RefinementTable {
+        let dfa = self.to_dfa();
+        let alphabet = dfa.alphabet();
+
+        let states = dfa.reachable_states().into_iter().collect::<Vec<State>>();
+        let index: HashMap<State, usize> =
+            states.iter().enumerate().map(|(i, &s)| (s, i)).collect();
+        let dead = states.len();
+        let mut table = vec![vec![dead; alphabet.len()]; dead + 1];
+        for (i, &s) in states.iter().enumerate() {
+            for (k, &c) in alphabet.iter().enumerate() {
+                if let Some(next) = dfa.next_state(s, c) {
+                    table[i][k] = index[&next];
+                }
+            }
+        }
+        let accepting = (0..=dead)
+            .map(|q| q != dead && dfa.is_accepting(states[q]))
+            .collect::<Vec<bool>>();
+
+        RefinementTable {
+            dfa,
+            alphabet,
+            states,
+            index,
+            table,
+            accepting,
+        }
+    }
+    // Hopcroft's refinement of this automaton's DFA, recorded round by round.
+    pub fn partition_trace(&self) -> PartitionTrace {
+        let RefinementTable {
+            dfa,
+            alphabet,
+            states,
+            table,
+            accepting,
+            ..
+        } = self.refinement_table();
+        let (block_of, initial, rounds) = hopcroft::partition_traced(&table, &accepting);
+        PartitionTrace {
+            dfa,
+            alphabet,
+            states,
+            initial,
+            rounds,
+            block_of,
+        }
+    }
+    // Replaces this automaton with the minimal DFA for its language.  States are renumbered breadth-first from 0, the
+    // starting state, and states that cannot lead to acceptance are left out, so missing transitions still mean rejection.
+    pub fn minimize(&mut self) {
+        // Hopcroft's algorithm partitions the states of the DFA so that all states
+        // which have the same response to input are in the same partition.
+        let RefinementTable {
+            dfa,
+            alphabet,
+            states,
+            index,
+            table,
+            accepting,
+        } = self.refinement_table();
+        let dead = states.len();
+
+        let block_of = hopcroft::partition(&table, &accepting);
+        let mut representative = HashMap::new();
+        for q in (0..=dead).rev() {
+            representative.insert(block_of[q], q);
+        }
+
+        // Read the quotient automaton off the blocks, skipping the block of the dead state.
+        let mut fa = FA::new();
+        let start_block = block_of[index[&dfa.starting]];
+        let mut number = HashMap::new();
+        number.insert(start_block, 0);
+        fa.add_state(0);
+        let mut todo = VecDeque::new();
+        if start_block != block_of[dead] {
+            todo.push_back(start_block);
+        }
+
+        while let Some(block) = todo.pop_front() {
+            let q = representative[&block];
+            let id = number[&block];
+            if accepting[q] {
+                fa.add_acceptor(id);
+            }
+            for (k, &c) in alphabet.iter().enumerate() {
+                let next = block_of[table[q][k]];
+                if next == block_of[dead] {
+                    continue;
+                }
+                let next_id = match number.get(&next) {
+                    Some(&next_id) => next_id,
+                    None => {
+                        let next_id = number.len();
+                        number.insert(next, next_id);
+                        fa.add_state(next_id);
+                        todo.push_back(next);
+                        next_id
+                    }
+                };
+                fa.add_transition(Transition::from(Symbol::Char(c), id, next_id));
+            }
+        }
+
+        fa.set_start(0);
+        *self = fa;
+    }
+    // pub fn test(&self) {
+    //     let dfa = self.subset_construction();
+    //     for (stateset, transits) in dfa {
+    //         for t in transits {
+    //             println!("{:?}: {:?} -- {:?} -> {:?}", stateset, t.begin, t.sym, t.end);
+    //         }
+    //     }
+    // }
+}
+
+// Writes a set of NFA states as `{0,1,3}`.
+pub fn subset_label(subset: &StateSet<State>) -> String {
+    let states = subset
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+    format!("{{{}}}", states.join(","))
+}
+
+// A simple pretty printing of a finite automata.
+impl std::fmt::Display for FA {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use std::fmt::Write;
+        use std::iter::FromIterator;
+        let mut v = Vec::from_iter(self.graph.iter());
+        v.sort_by(|a, b| (a.0).partial_cmp(b.0).unwrap());
+
+        let mut output = String::new();
+        let mut state_count = 0;
+        let mut transition_count = 0;
+
+        for (state, transitions) in v {
+            state_count += 1;
+            // `{:#}` notes the NFA states behind each state of a determinized automaton, in a comment the reader
+            // skips.
+            if let (true, Some(subset)) = (f.alternate(), self.subsets.get(state)) {
+                writeln!(output, "// {}", subset_label(subset))?;
+            }
+            writeln!(
+                output,
+                "{} {} {}",
+                state,
+                {
+                    if self.accepting.contains(state) {
+                        fa_reader::ACCEPT_SYMBOL
+                    } else {
+                        fa_reader::STATE_SYMBOL
+                    }
+                },
+                transitions.len()
+            )?;
+
+            for t in transitions {
+                transition_count += 1;
+                writeln!(
+                    output,
+                    "{} -> {}",
+                    {
+                        match t.sym() {
+                            Symbol::Char(c) => c,
+                            Symbol::Empty => ' ',
+                        }
+                    },
+                    t.end()
+                )?;
+            }
+
+            writeln!(output)?;
+        }
+
+        writeln!(
+            output,
+            "// {} states, {} transitions",
+            state_count, transition_count
+        )?;
+
+        write!(f, "{}", output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivalence::{equivalence, Equivalence};
+    use crate::regex_parser::parse_to_nfa;
+
+    #[test]
+    fn subsets_are_cleared_when_the_automaton_changes() {
+        let mut dfa = parse_to_nfa("a|b").unwrap().dfa_from();
+        assert!(dfa.subset_of(dfa.starting()).is_some());
+        assert!(format!("{:#}", dfa).contains("// {"));
+
+        let fresh = dfa.fresh_state();
+        dfa.add_state(fresh);
+        assert!(dfa.subset_of(dfa.starting()).is_none());
+        assert!(!format!("{:#}", dfa).contains("// {"));
+    }
+
+    #[test]
+    fn remove_epsilons_keeps_the_language() {
+        for regex in ["(a|b)*abb", "a?b?", "(ε|a)*b", "a*&(aa)*", "ε"] {
+            let nfa = parse_to_nfa(regex).unwrap();
+            let removed = nfa.remove_epsilons();
+            assert!(
+                removed.delta().iter().all(|t| t.sym() != Symbol::Empty),
+                "{}",
+                regex
+            );
+            assert_eq!(
+                equivalence(&removed, &nfa),
+                Equivalence::Equivalent,
+                "{}",
+                regex
+            );
+            // States keep their numbers, so the start is the NFA's own.
+            assert_eq!(removed.starting(), nfa.starting());
+            assert!(removed.states().iter().all(|s| nfa.states().contains(s)));
+        }
+    }
+
+    #[test]
+    fn trim_removes_dead_and_unreachable_states() {
+        let mut fa = FA::new();
+        for s in 10..15 {
+            fa.add_state(s);
+        }
+        // 12 is dead, looping on itself with no way to acceptance, and nothing reaches 13.
+        for (c, p, q) in [
+            ('a', 10, 11),
+            ('b', 11, 14),
+            ('c', 10, 12),
+            ('c', 12, 12),
+            ('a', 13, 14),
+        ] {
+            fa.add_transition(Transition::from(Symbol::Char(c), p, q));
+        }
+        fa.add_acceptor(13);
+        fa.add_acceptor(14);
+        fa.set_start(10);
+
+        let mut trimmed = fa.clone();
+        trimmed.trim();
+        assert_eq!(trimmed.starting(), 0);
+        assert_eq!(trimmed.states(), &vec![0, 1, 2]);
+        assert_eq!(trimmed.delta().len(), 2);
+        assert_eq!(trimmed.accepting(), &vec![2]);
+        assert_eq!(equivalence(&trimmed, &fa), Equivalence::Equivalent);
+    }
+
+    #[test]
+    fn trim_keeps_the_language() {
+        for regex in ["(a|b)*abb", "a?b?", "a*&(aa)*", "ab&ba"] {
+            let nfa = parse_to_nfa(regex).unwrap();
+            let mut trimmed = nfa.clone();
+            trimmed.trim();
+            assert_eq!(
+                equivalence(&trimmed, &nfa),
+                Equivalence::Equivalent,
+                "{}",
+                regex
+            );
+            assert!(trimmed.num_states() <= nfa.num_states());
+        }
+        // An automaton that accepts nothing keeps only its start.
+        let mut empty = parse_to_nfa("ab&ba").unwrap();
+        empty.trim();
+        assert_eq!(empty.states(), &vec![0]);
+        assert!(empty.delta().is_empty());
+    }
+}
@@ -1,42 +1,317 @@
-// Converts finite automata into dot language files.
-
-use crate::symbol::Symbol;
-use std::fmt::Write;
-
-pub fn draw_fa(fa: crate::fa::FA) -> Result<String, Box<dyn std::error::Error>> {
-    let mut output = String::new();
-
-    writeln!(output, "digraph {{")?;
-    writeln!(output, "rankdir=LR")?;
-
-    let mut accepting = fa.accepting().clone();
-    accepting.sort();
-    let mut acceptors_string = String::new();
-    for a in accepting {
-        acceptors_string.push(' ');
-        acceptors_string.push(char::from_digit(a as u32, 10).unwrap());
-    }
-
-    // The string `acceptors` has a space before it; it's just easy.  Remember that.
-    writeln!(output, "node [shape = doublecircle];{};", acceptors_string)?;
-    writeln!(output, "node [shape = circle];")?;
-
-    // let mut states = fa.states();
-    // states.sort();
-    // for state in states {
-
-    // }
-
-    for t in fa.delta() {
-        writeln!(output, "{} -> {} [label=\"'{}'\"]", t.start(), t.end(), {
-            match t.sym() {
-                Symbol::Char(c) => c,
-                Symbol::Empty => '\u{03B5}',
-            }
-        })?;
-    }
-
-    writeln!(output, "}}")?;
-
-    Ok(output)
-}
+// Converts finite automata into dot language files.
+
+use crate::diff::{Diff, Difference};
+use crate::fa::{subset_label, State, FA};
+use crate::partition_trace::PartitionTrace;
+use crate::symbol::Symbol;
+use crate::thompsons::Fragment;
+use std::fmt::Write;
+
+pub fn draw_fa(fa: crate::fa::FA) -> Result<String, Box<dyn std::error::Error>> {
+    draw(&fa, false)
+}
+
+// Like `draw_fa`, but each state of a determinized automaton is labelled with the NFA states it stands for, as in
+// `{0,1,3}`, under its own number.
+pub fn draw_fa_with_subsets(fa: &FA) -> Result<String, Box<dyn std::error::Error>> {
+    draw(fa, true)
+}
+
+fn draw(fa: &FA, subsets: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let mut output = String::new();
+
+    writeln!(output, "digraph {{")?;
+    writeln!(output, "rankdir=LR")?;
+
+    let mut accepting = fa.accepting().clone();
+    accepting.sort();
+    let mut acceptors_string = String::new();
+    for a in accepting {
+        write!(acceptors_string, " {}", a)?;
+    }
+
+    // The string `acceptors` has a space before it; it's just easy.  Remember that.
+    writeln!(output, "node [shape = doublecircle];{};", acceptors_string)?;
+    writeln!(output, "node [shape = circle];")?;
+
+    // let mut states = fa.states();
+    // states.sort();
+    // for state in states {
+
+    // }
+    if subsets {
+        let mut states = fa.states().clone();
+        states.sort_unstable();
+        for state in states {
+            if let Some(subset) = fa.subset_of(state) {
+                writeln!(
+                    output,
+                    "{} [label=\"{}\\n{}\"]",
+                    state,
+                    state,
+                    subset_label(subset)
+                )?;
+            }
+        }
+    }
+
+    for t in fa.delta() {
+        writeln!(
+            output,
+            "{} -> {} [label=\"'{}'\"]",
+            t.start(),
+            t.end(),
+            label(t.sym())
+        )?;
+    }
+
+    writeln!(output, "}}")?;
+
+    Ok(output)
+}
+
+fn label(sym: Symbol) -> String {
+    match sym {
+        // Quotes and backslashes would end or escape the dot label.
+        Symbol::Char(c) if c == '"' || c == '\\' => format!("\\{}", c),
+        Symbol::Char(c) => c.to_string(),
+        Symbol::Empty => '\u{03B5}'.to_string(),
+    }
+}
+
+// Draws the new automaton with the differences from the old one colored in: added states and transitions in green,
+// removed ones dashed in red, and states whose acceptance changed in blue.  Removed states are labelled `old N`.
+pub fn draw_diff(old: &FA, new: &FA, diff: &Diff) -> Result<String, Box<dyn std::error::Error>> {
+    let mut output = String::new();
+
+    writeln!(output, "digraph {{")?;
+    writeln!(output, "rankdir=LR")?;
+
+    let added = |s: State| diff.differences.contains(&Difference::Added(s));
+    let mut states = new.reachable_states().into_iter().collect::<Vec<State>>();
+    states.sort_unstable();
+    for &s in &states {
+        let shape = if new.is_accepting(s) {
+            "doublecircle"
+        } else {
+            "circle"
+        };
+        let accept_changed = diff
+            .differences
+            .iter()
+            .any(|d| matches!(d, Difference::Accepting { state, .. } if *state == s));
+        let color = if added(s) {
+            "green"
+        } else if accept_changed {
+            "blue"
+        } else {
+            "black"
+        };
+        writeln!(
+            output,
+            "n{} [label=\"{}\", shape={}, color={}]",
+            s, s, shape, color
+        )?;
+    }
+    for d in &diff.differences {
+        if let Difference::Removed(s) = d {
+            let shape = if old.is_accepting(*s) {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            writeln!(
+                output,
+                "o{} [label=\"old {}\", shape={}, color=red, style=dashed]",
+                s, s, shape
+            )?;
+        }
+    }
+
+    // An old state is drawn as its new counterpart if it has one.
+    let old_node = |s: State| match diff.counterpart(s) {
+        Some(n) => format!("n{}", n),
+        None => format!("o{}", s),
+    };
+    let retargeted = |s: State, c: char| {
+        diff.differences.iter().any(
+            |d| matches!(d, Difference::Retargeted { state, sym, .. } if *state == s && *sym == c),
+        )
+    };
+    for &s in &states {
+        for t in new.transitions_of(s).into_iter().flatten() {
+            let changed = match t.sym() {
+                Symbol::Char(c) => added(s) || retargeted(s, c),
+                Symbol::Empty => added(s),
+            };
+            let color = if changed { "green" } else { "black" };
+            writeln!(
+                output,
+                "n{} -> n{} [label=\"'{}'\", color={}]",
+                s,
+                t.end(),
+                label(t.sym()),
+                color
+            )?;
+        }
+    }
+    // The old transitions that are gone: every one out of a removed state, and the old targets of retargeted ones.
+    for d in &diff.differences {
+        let (from, transitions) = match d {
+            Difference::Removed(s) => (
+                format!("o{}", s),
+                old.transitions_of(*s).cloned().unwrap_or_default(),
+            ),
+            Difference::Retargeted {
+                state,
+                sym,
+                before: Some(_),
+                ..
+            } => {
+                let original = diff.original(*state).unwrap();
+                let gone = old
+                    .transitions_of(original)
+                    .into_iter()
+                    .flatten()
+                    .filter(|t| t.sym() == Symbol::Char(*sym))
+                    .copied()
+                    .collect();
+                (format!("n{}", state), gone)
+            }
+            _ => continue,
+        };
+        for t in transitions {
+            writeln!(
+                output,
+                "{} -> {} [label=\"'{}'\", color=red, style=dashed]",
+                from,
+                old_node(t.end()),
+                label(t.sym())
+            )?;
+        }
+    }
+
+    writeln!(output, "}}")?;
+
+    Ok(output)
+}
+
+// Draws the DFA a partition refinement ran on, with its states clustered by the equivalence class they end up in.
+pub fn draw_partition(trace: &PartitionTrace) -> Result<String, Box<dyn std::error::Error>> {
+    let mut output = String::new();
+
+    writeln!(output, "digraph {{")?;
+    writeln!(output, "rankdir=LR")?;
+
+    for (i, class) in trace.classes().iter().enumerate() {
+        let members = class.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let dead = if trace.is_dead(class[0]) { " dead" } else { "" };
+        writeln!(output, "subgraph cluster_{} {{", i)?;
+        writeln!(output, "label=\"{{{}}}{}\"", members.join(","), dead)?;
+        for &s in class {
+            let shape = if trace.dfa.is_accepting(s) {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            writeln!(output, "{} [shape={}]", s, shape)?;
+        }
+        writeln!(output, "}}")?;
+    }
+    for &s in &trace.states {
+        for t in trace.dfa.transitions_of(s).into_iter().flatten() {
+            writeln!(
+                output,
+                "{} -> {} [label=\"'{}'\"]",
+                t.start(),
+                t.end(),
+                label(t.sym())
+            )?;
+        }
+    }
+
+    writeln!(output, "}}")?;
+
+    Ok(output)
+}
+
+// Draws a Thompson NFA with the piece built for each operator boxed in a cluster labelled with its subexpression, the
+// clusters nested as the subexpressions are.  A state is drawn in the innermost fragment it belongs to.
+pub fn draw_thompson(
+    fa: &FA,
+    fragments: &[Fragment],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut output = String::new();
+
+    writeln!(output, "digraph {{")?;
+    writeln!(output, "rankdir=LR")?;
+
+    // Fragments are listed after the ones inside them, so the first to hold a state is the innermost.
+    let mut states = fa.states().clone();
+    states.sort_unstable();
+    let mut owned = vec![Vec::new(); fragments.len()];
+    for &s in &states {
+        match fragments.iter().position(|f| f.states.contains(&s)) {
+            Some(i) => owned[i].push(s),
+            None => node(&mut output, fa, s)?,
+        }
+    }
+    for (i, fragment) in fragments.iter().enumerate() {
+        if fragment.parent.is_none() {
+            cluster(&mut output, fa, fragments, &owned, i)?;
+        }
+    }
+    for t in fa.delta() {
+        writeln!(
+            output,
+            "{} -> {} [label=\"'{}'\"]",
+            t.start(),
+            t.end(),
+            label(t.sym())
+        )?;
+    }
+
+    writeln!(output, "}}")?;
+
+    Ok(output)
+}
+
+fn node(output: &mut String, fa: &FA, s: State) -> std::fmt::Result {
+    let shape = if fa.is_accepting(s) {
+        "doublecircle"
+    } else {
+        "circle"
+    };
+    writeln!(output, "{} [shape={}]", s, shape)
+}
+
+// Fragments with no states, like those inside `&` and `~`, are not drawn.
+fn cluster(
+    output: &mut String,
+    fa: &FA,
+    fragments: &[Fragment],
+    owned: &[Vec<State>],
+    i: usize,
+) -> std::fmt::Result {
+    let fragment = &fragments[i];
+    if fragment.states.is_empty() {
+        return Ok(());
+    }
+    let text = fragment
+        .label
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => format!("\\{}", c),
+            c => c.to_string(),
+        })
+        .collect::<String>();
+    writeln!(output, "subgraph cluster_{} {{", i)?;
+    writeln!(output, "label=\"{}\"", text)?;
+    for &s in &owned[i] {
+        node(output, fa, s)?;
+    }
+    for &child in &fragment.children {
+        cluster(output, fa, fragments, owned, child)?;
+    }
+    writeln!(output, "}}")
+}
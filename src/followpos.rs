@@ -0,0 +1,261 @@
+// The direct construction of a DFA from a regex, without an NFA in between (Dragon Book, section 3.9).  Every
+// character or class in the regex is a position, numbered from 1 left to right, and the regex is augmented to `(r)#`
+// so that acceptance is reaching the end marker `#`.  Each DFA state is a set of positions that can match the next
+// character: the start is firstpos of the whole regex, and on a character it moves to the union of followpos over the
+// positions in the state that match that character.
+
+use crate::fa::{State, FA};
+use crate::symbol::Symbol;
+use crate::thompsons::Expr;
+use crate::transition::Transition;
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+#[derive(Debug, Clone)]
+pub struct Positions {
+    // The characters each position matches, by position number less one.  The end marker, the last position,
+    // matches none.
+    pub symbols: Vec<Vec<char>>,
+    // The positions that can match the first character of a string.
+    pub first: BTreeSet<usize>,
+    // The positions that can match the character after one matched at each position.
+    pub follow: Vec<BTreeSet<usize>>,
+}
+
+// nullable, firstpos and lastpos of a subexpression.
+struct Node {
+    nullable: bool,
+    first: BTreeSet<usize>,
+    last: BTreeSet<usize>,
+}
+
+impl Positions {
+    // Fails on intersection and complement, which have no positions to follow.
+    pub fn new(expr: &Expr) -> Result<Self, String> {
+        let mut positions = Positions {
+            symbols: Vec::new(),
+            first: BTreeSet::new(),
+            follow: Vec::new(),
+        };
+        let root = positions.visit(expr)?;
+        // Concatenating the end marker makes it follow every last position.
+        let end = positions.add_position(Vec::new());
+        positions.add_follow(&root.last, &[end].into());
+        positions.first = root.first;
+        if root.nullable {
+            positions.first.insert(end);
+        }
+        Ok(positions)
+    }
+    // The end marker's position.
+    pub fn end(&self) -> usize {
+        self.symbols.len()
+    }
+    fn add_position(&mut self, chars: Vec<char>) -> usize {
+        self.symbols.push(chars);
+        self.follow.push(BTreeSet::new());
+        self.symbols.len()
+    }
+    fn add_follow(&mut self, from: &BTreeSet<usize>, to: &BTreeSet<usize>) {
+        for &p in from {
+            self.follow[p - 1].extend(to);
+        }
+    }
+    fn visit(&mut self, expr: &Expr) -> Result<Node, String> {
+        let leaf = |p: usize| Node {
+            nullable: false,
+            first: [p].into(),
+            last: [p].into(),
+        };
+        Ok(match expr {
+            Expr::Empty | Expr::Just(Symbol::Empty) => Node {
+                nullable: true,
+                first: BTreeSet::new(),
+                last: BTreeSet::new(),
+            },
+            Expr::Just(Symbol::Char(c)) => leaf(self.add_position(vec![*c])),
+            Expr::Class(class) => leaf(self.add_position(class.clone())),
+            Expr::Or(e1, e2) => {
+                let (c1, c2) = (self.visit(e1)?, self.visit(e2)?);
+                Node {
+                    nullable: c1.nullable || c2.nullable,
+                    first: &c1.first | &c2.first,
+                    last: &c1.last | &c2.last,
+                }
+            }
+            Expr::And(e1, e2) => {
+                let (c1, c2) = (self.visit(e1)?, self.visit(e2)?);
+                self.add_follow(&c1.last, &c2.first);
+                Node {
+                    nullable: c1.nullable && c2.nullable,
+                    first: if c1.nullable {
+                        &c1.first | &c2.first
+                    } else {
+                        c1.first
+                    },
+                    last: if c2.nullable {
+                        &c1.last | &c2.last
+                    } else {
+                        c2.last
+                    },
+                }
+            }
+            Expr::Star(e) | Expr::Plus(e) => {
+                let c = self.visit(e)?;
+                self.add_follow(&c.last, &c.first);
+                Node {
+                    nullable: matches!(expr, Expr::Star(_)) || c.nullable,
+                    ..c
+                }
+            }
+            Expr::QMark(e) => Node {
+                nullable: true,
+                ..self.visit(e)?
+            },
+            Expr::Group(_, e) => self.visit(e)?,
+            Expr::Intersect(..) | Expr::Complement(_) => {
+                return Err("the followpos construction does not support & or ~".to_string())
+            }
+        })
+    }
+    // The DFA whose states are the sets of positions reachable from firstpos, numbered in breadth-first order.
+    pub fn dfa(&self) -> FA {
+        let alphabet = self
+            .symbols
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<BTreeSet<char>>();
+
+        let mut fa = FA::new();
+        let mut ids: HashMap<BTreeSet<usize>, State> = HashMap::new();
+        let mut todo = VecDeque::new();
+        ids.insert(self.first.clone(), 0);
+        fa.add_state(0);
+        fa.set_start(0);
+        todo.push_back(self.first.clone());
+
+        while let Some(set) = todo.pop_front() {
+            let from = ids[&set];
+            if set.contains(&self.end()) {
+                fa.add_acceptor(from);
+            }
+            for &c in &alphabet {
+                let next = set
+                    .iter()
+                    .filter(|&&p| self.symbols[p - 1].contains(&c))
+                    .flat_map(|&p| self.follow[p - 1].iter().copied())
+                    .collect::<BTreeSet<usize>>();
+                if next.is_empty() {
+                    continue;
+                }
+                let to = match ids.get(&next) {
+                    Some(&to) => to,
+                    None => {
+                        let to = ids.len();
+                        ids.insert(next.clone(), to);
+                        fa.add_state(to);
+                        todo.push_back(next);
+                        to
+                    }
+                };
+                fa.add_transition(Transition::from(Symbol::Char(c), from, to));
+            }
+        }
+
+        fa
+    }
+}
+
+// Writes the followpos table, one row per position, after firstpos of the whole regex.
+impl std::fmt::Display for Positions {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let set = |s: &BTreeSet<usize>| {
+            let items = s.iter().map(|p| p.to_string()).collect::<Vec<String>>();
+            format!("{{{}}}", items.join(", "))
+        };
+        writeln!(f, "firstpos: {}", set(&self.first))?;
+        writeln!(f, "{:<10}{:<10}followpos", "position", "symbol")?;
+        for (i, chars) in self.symbols.iter().enumerate() {
+            let symbol = match chars.as_slice() {
+                [] if i + 1 == self.end() => "#".to_string(),
+                [c] => c.to_string(),
+                class => Expr::Class(class.to_vec()).to_string(),
+            };
+            writeln!(f, "{:<10}{:<10}{}", i + 1, symbol, set(&self.follow[i]))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivalence::{equivalence, Equivalence};
+    use crate::regex_parser::{parse_to_dfa, parse_to_expr};
+
+    fn positions(regex: &str) -> Positions {
+        Positions::new(&parse_to_expr(regex).unwrap()).unwrap()
+    }
+
+    fn set(items: &[usize]) -> BTreeSet<usize> {
+        items.iter().copied().collect()
+    }
+
+    // Figure 3.60 of the Dragon Book.
+    #[test]
+    fn dragon_book_followpos() {
+        let positions = positions("(a|b)*abb");
+        assert_eq!(positions.first, set(&[1, 2, 3]));
+        assert_eq!(positions.end(), 6);
+        let symbols = positions
+            .symbols
+            .iter()
+            .map(|s| s.as_slice())
+            .collect::<Vec<&[char]>>();
+        assert_eq!(
+            symbols,
+            vec![&['a'][..], &['b'], &['a'], &['b'], &['b'], &[]]
+        );
+        assert_eq!(
+            positions.follow,
+            vec![
+                set(&[1, 2, 3]),
+                set(&[1, 2, 3]),
+                set(&[4]),
+                set(&[5]),
+                set(&[6]),
+                set(&[]),
+            ]
+        );
+        assert_eq!(positions.dfa().num_states(), 4);
+    }
+
+    #[test]
+    fn dfa_agrees_with_thompson() {
+        for regex in [
+            "(a|b)*abb",
+            "a(b|c)+d?",
+            "(ab|a)*b",
+            "[abc]*x|ε",
+            "a?b?c?",
+            "((a*)*)*",
+        ] {
+            let dfa = positions(regex).dfa();
+            assert!(dfa.is_deterministic(), "{}", regex);
+            let expected = parse_to_dfa(regex).unwrap();
+            assert_eq!(
+                equivalence(&dfa, &expected),
+                Equivalence::Equivalent,
+                "{}",
+                regex
+            );
+        }
+    }
+
+    #[test]
+    fn intersection_and_complement_are_rejected() {
+        assert!(Positions::new(&parse_to_expr("a&b").unwrap()).is_err());
+        assert!(Positions::new(&parse_to_expr("~a").unwrap()).is_err());
+    }
+}
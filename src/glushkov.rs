@@ -0,0 +1,216 @@
+// The Glushkov, or position, automaton: an NFA with no empty transitions and one state per position of the regex, plus
+// a start state.  It comes from the same firstpos and followpos the direct DFA construction uses.  State 0 is the
+// start and state p is "just matched position p", so every transition into p is on the characters p matches, and a
+// state accepts when the end marker follows it.
+//
+// Because all the transitions into a state share their characters, a set of states can be stepped as a bitset: the
+// next set is everything the current set can be followed by, masked down to the states entered on the character.
+
+use crate::fa::FA;
+use crate::followpos::Positions;
+use crate::symbol::Symbol;
+use crate::transition::Transition;
+
+use std::collections::HashMap;
+
+// The targets of state 0's transitions are the first positions, and of state p's the positions that follow p,
+// leaving out the end marker in both cases.
+fn successors(positions: &Positions) -> Vec<Vec<usize>> {
+    std::iter::once(&positions.first)
+        .chain(positions.follow.iter())
+        .take(positions.end())
+        .map(|set| {
+            set.iter()
+                .copied()
+                .filter(|&p| p != positions.end())
+                .collect()
+        })
+        .collect()
+}
+
+fn accepting(positions: &Positions) -> Vec<usize> {
+    std::iter::once(&positions.first)
+        .chain(positions.follow.iter())
+        .take(positions.end())
+        .enumerate()
+        .filter(|(_, set)| set.contains(&positions.end()))
+        .map(|(state, _)| state)
+        .collect()
+}
+
+// An NFA with exactly n + 1 states for the n positions of the regex.
+pub fn nfa(positions: &Positions) -> FA {
+    let mut fa = FA::new();
+    for state in 0..positions.end() {
+        fa.add_state(state);
+    }
+    fa.set_start(0);
+    for state in accepting(positions) {
+        fa.add_acceptor(state);
+    }
+    for (from, targets) in successors(positions).into_iter().enumerate() {
+        for to in targets {
+            for &c in &positions.symbols[to - 1] {
+                fa.add_transition(Transition::from(Symbol::Char(c), from, to));
+            }
+        }
+    }
+    fa
+}
+
+// Simulates the Glushkov automaton on sets of states held as bits, 64 states to a word.
+#[derive(Debug, Clone)]
+pub struct BitMatcher {
+    words: usize,
+    // follow[k][v] is the union of the successors of the states in the kth group of eight whose bits are set in v,
+    // so stepping a set takes one lookup per byte instead of one per state.
+    follow: Vec<Vec<Vec<u64>>>,
+    // The states entered on each character.
+    entered: HashMap<char, Vec<u64>>,
+    accepting: Vec<u64>,
+}
+
+impl BitMatcher {
+    pub fn new(positions: &Positions) -> Self {
+        let states = positions.end();
+        let words = states.div_ceil(64);
+        let bits = |states: &[usize]| {
+            let mut set = vec![0u64; words];
+            for &s in states {
+                set[s / 64] |= 1 << (s % 64);
+            }
+            set
+        };
+
+        let successors = successors(positions)
+            .iter()
+            .map(|s| bits(s))
+            .collect::<Vec<Vec<u64>>>();
+        let follow = (0..states.div_ceil(8))
+            .map(|k| {
+                let mut table = vec![vec![0u64; words]; 256];
+                for v in 1..256usize {
+                    // Each entry is a smaller one plus the successors of its lowest state.
+                    let state = 8 * k + v.trailing_zeros() as usize;
+                    let mut set = table[v & (v - 1)].clone();
+                    if let Some(succ) = successors.get(state) {
+                        set.iter_mut().zip(succ).for_each(|(w, s)| *w |= s);
+                    }
+                    table[v] = set;
+                }
+                table
+            })
+            .collect();
+
+        let mut entered: HashMap<char, Vec<usize>> = HashMap::new();
+        for (i, chars) in positions.symbols.iter().enumerate() {
+            for &c in chars {
+                entered.entry(c).or_default().push(i + 1);
+            }
+        }
+
+        Self {
+            words,
+            follow,
+            entered: entered.into_iter().map(|(c, s)| (c, bits(&s))).collect(),
+            accepting: bits(&accepting(positions)),
+        }
+    }
+    pub fn is_match(&self, input: &str) -> bool {
+        let mut current = vec![0u64; self.words];
+        current[0] = 1;
+
+        for c in input.chars() {
+            let entered = match self.entered.get(&c) {
+                Some(entered) => entered,
+                None => return false,
+            };
+            let mut next = vec![0u64; self.words];
+            for (k, table) in self.follow.iter().enumerate() {
+                let byte = (current[k / 8] >> (8 * (k % 8))) & 0xff;
+                if byte != 0 {
+                    next.iter_mut()
+                        .zip(&table[byte as usize])
+                        .for_each(|(w, s)| *w |= s);
+                }
+            }
+            next.iter_mut().zip(entered).for_each(|(w, e)| *w &= e);
+            if next.iter().all(|&w| w == 0) {
+                return false;
+            }
+            current = next;
+        }
+        current.iter().zip(&self.accepting).any(|(w, a)| w & a != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivalence::{equivalence, Equivalence};
+    use crate::regex_parser::{parse_to_dfa, parse_to_expr};
+
+    const REGEXES: [&str; 6] = [
+        "(a|b)*abb",
+        "a(b|c)+d?",
+        "(ab|a)*b",
+        "[abc]*x|ε",
+        "a?b?c?",
+        "((a*)*)*",
+    ];
+
+    fn positions(regex: &str) -> Positions {
+        Positions::new(&parse_to_expr(regex).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn nfa_agrees_with_thompson() {
+        for regex in REGEXES {
+            let positions = positions(regex);
+            let nfa = nfa(&positions);
+            assert_eq!(nfa.num_states(), positions.end(), "{}", regex);
+            assert!(
+                nfa.delta().iter().all(|t| t.sym() != Symbol::Empty),
+                "{}",
+                regex
+            );
+            let expected = parse_to_dfa(regex).unwrap();
+            assert_eq!(
+                equivalence(&nfa, &expected),
+                Equivalence::Equivalent,
+                "{}",
+                regex
+            );
+        }
+    }
+
+    #[test]
+    fn bit_matcher_agrees_with_thompson() {
+        for regex in REGEXES {
+            let matcher = BitMatcher::new(&positions(regex));
+            let expected = parse_to_dfa(regex).unwrap();
+            for input in [
+                "", "a", "b", "abb", "babb", "abcd", "abab", "aab", "x", "ccx", "ac", "bc",
+            ] {
+                assert_eq!(
+                    matcher.is_match(input),
+                    expected.dfa_accepts(input.to_string()),
+                    "{} on {:?}",
+                    regex,
+                    input
+                );
+            }
+        }
+    }
+
+    // More than 64 positions, so the state sets take more than one word.
+    #[test]
+    fn bit_matcher_across_words() {
+        let regex = format!("({})*", "ab".repeat(40));
+        let matcher = BitMatcher::new(&positions(&regex));
+        assert!(matcher.is_match(""));
+        assert!(matcher.is_match(&"ab".repeat(80)));
+        assert!(!matcher.is_match(&"ab".repeat(41)));
+        assert!(!matcher.is_match(&"ab".repeat(39)));
+    }
+}
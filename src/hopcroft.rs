@@ -0,0 +1,186 @@
+// Hopcroft's partition refinement, used by `FA::minimize`.
+
+use std::collections::{BTreeMap, HashSet};
+
+// One splitter taken off the waiting list that split at least one block.
+#[derive(Debug, Clone)]
+pub struct Round {
+    // The states of the splitter block, and the index of its symbol.
+    pub splitter: Vec<usize>,
+    pub symbol: usize,
+    // Each block that was split, as the states that move into the splitter on the symbol and those that do not.
+    pub splits: Vec<(Vec<usize>, Vec<usize>)>,
+    // The partition after the round.
+    pub blocks: Vec<Vec<usize>>,
+}
+
+// `table[q][k]` is the state reached from `q` on the `k`th symbol; the DFA must be complete, so every entry is filled.
+// Returns the block each state ends up in.  States in the same block accept exactly the same strings.
+pub fn partition(table: &[Vec<usize>], accepting: &[bool]) -> Vec<usize> {
+    refine(table, accepting, &mut None)
+}
+
+// The same, along with the partition it starts from and every round that split a block.
+pub fn partition_traced(
+    table: &[Vec<usize>],
+    accepting: &[bool],
+) -> (Vec<usize>, Vec<Vec<usize>>, Vec<Round>) {
+    let mut rounds = Some(Vec::new());
+    let block_of = refine(table, accepting, &mut rounds);
+    (
+        block_of,
+        initial_blocks(accepting),
+        rounds.unwrap_or_default(),
+    )
+}
+
+// The accepting/non-accepting split, leaving out whichever side is empty.
+fn initial_blocks(accepting: &[bool]) -> Vec<Vec<usize>> {
+    let (acc, rej): (Vec<usize>, Vec<usize>) = (0..accepting.len()).partition(|&q| accepting[q]);
+    vec![acc, rej]
+        .into_iter()
+        .filter(|b| !b.is_empty())
+        .collect()
+}
+
+fn refine(table: &[Vec<usize>], accepting: &[bool], trace: &mut Option<Vec<Round>>) -> Vec<usize> {
+    let n = table.len();
+    let symbols = table.first().map_or(0, |row| row.len());
+
+    // inverse[k][q] lists the states that move to q on the kth symbol.
+    let mut inverse = vec![vec![Vec::new(); n]; symbols];
+    for (p, row) in table.iter().enumerate() {
+        for (k, &q) in row.iter().enumerate() {
+            inverse[k][q].push(p);
+        }
+    }
+
+    // Start from the accepting/non-accepting split.
+    let mut blocks = initial_blocks(accepting);
+    let mut block_of = vec![0; n];
+    for (b, block) in blocks.iter().enumerate() {
+        for &q in block {
+            block_of[q] = b;
+        }
+    }
+
+    // The waiting list of splitters (block, symbol).  Only the smaller of the first two blocks is needed:
+    // whatever it fails to split, the other block would not split either.
+    let mut waiting: Vec<(usize, usize)> = Vec::new();
+    let mut in_waiting: HashSet<(usize, usize)> = HashSet::new();
+    if blocks.len() == 2 {
+        let smaller = if blocks[0].len() <= blocks[1].len() {
+            0
+        } else {
+            1
+        };
+        for k in 0..symbols {
+            waiting.push((smaller, k));
+            in_waiting.insert((smaller, k));
+        }
+    }
+
+    while let Some((splitter, k)) = waiting.pop() {
+        in_waiting.remove(&(splitter, k));
+
+        // Group the states that move into the splitter on symbol k by the block they are currently in.
+        let mut movers: BTreeMap<usize, HashSet<usize>> = BTreeMap::new();
+        for &q in &blocks[splitter] {
+            for &p in &inverse[k][q] {
+                movers.entry(block_of[p]).or_default().insert(p);
+            }
+        }
+
+        // The splitter may be split itself, so the trace needs a copy from before.
+        let splitter_states = match trace {
+            Some(_) => blocks[splitter].clone(),
+            None => Vec::new(),
+        };
+        let mut splits = Vec::new();
+        for (y, inside) in movers {
+            if inside.len() == blocks[y].len() {
+                continue;
+            }
+            // Block y has states on both sides of the splitter; the ones that do not move into it get a new block.
+            let (stay, leave): (Vec<usize>, Vec<usize>) =
+                blocks[y].iter().partition(|q| inside.contains(q));
+            let new = blocks.len();
+            for &q in &leave {
+                block_of[q] = new;
+            }
+            if trace.is_some() {
+                splits.push((stay.clone(), leave.clone()));
+            }
+            blocks[y] = stay;
+            blocks.push(leave);
+
+            for k2 in 0..symbols {
+                // If y was still waiting, both halves must be; otherwise the smaller half suffices.
+                let add = if in_waiting.contains(&(y, k2)) || blocks[new].len() <= blocks[y].len() {
+                    new
+                } else {
+                    y
+                };
+                if in_waiting.insert((add, k2)) {
+                    waiting.push((add, k2));
+                }
+            }
+        }
+        if let (Some(rounds), false) = (trace.as_mut(), splits.is_empty()) {
+            rounds.push(Round {
+                splitter: splitter_states,
+                symbol: k,
+                splits,
+                blocks: blocks.clone(),
+            });
+        }
+    }
+
+    block_of
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivalence::{equivalence, Equivalence};
+    use crate::regex_parser::parse_to_nfa;
+
+    fn minimal_states(regex: &str) -> usize {
+        let nfa = parse_to_nfa(regex).unwrap();
+        let mut fa = nfa.clone();
+        fa.minimize();
+        assert_eq!(equivalence(&fa, &nfa), Equivalence::Equivalent);
+        fa.num_states()
+    }
+
+    #[test]
+    fn known_minimal_dfas() {
+        // The Dragon Book's example (section 3.9.6).
+        assert_eq!(minimal_states("(a|b)*abb"), 4);
+        assert_eq!(minimal_states("(a|b)*a(a|b)"), 4);
+        assert_eq!(minimal_states("a*"), 1);
+        assert_eq!(minimal_states("(a|b)*"), 1);
+        // The dead state is left out.
+        assert_eq!(minimal_states("a(b|c)"), 3);
+        assert_eq!(minimal_states("ab|cb"), 3);
+    }
+
+    #[test]
+    fn partition_merges_equivalent_states() {
+        // States 1 and 2 both accept and loop to themselves; 0 moves to either of them.
+        let table = vec![vec![1, 2], vec![1, 1], vec![2, 2]];
+        let block_of = partition(&table, &[false, true, true]);
+        assert_eq!(block_of[1], block_of[2]);
+        assert_ne!(block_of[0], block_of[1]);
+    }
+
+    #[test]
+    fn partition_splits_by_future() {
+        // Over one symbol, 0 -> 1 -> 2 -> 2 with only 2 accepting: each state is a distance from acceptance.
+        let table = vec![vec![1], vec![2], vec![2]];
+        let block_of = partition(&table, &[false, false, true]);
+        assert_ne!(block_of[0], block_of[1]);
+        assert_ne!(block_of[1], block_of[2]);
+        assert_ne!(block_of[0], block_of[2]);
+    }
+}
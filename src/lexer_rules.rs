@@ -0,0 +1,214 @@
+// A lexer specification is an ordered list of named rules, one per line: the rule's name, whitespace, then its regex.
+// Lines starting with `//` are comments.  Earlier rules have priority, so when two rules match the same token, the
+// one listed first wins.
+//
+// IF if
+// IDENT (a|b|c|f|i)+
+
+use crate::fa::FA;
+use crate::regex_parser;
+
+use std::fmt::Write;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub regex: String,
+    pub nfa: FA,
+}
+
+// A rule that can never win: every string it matches is matched by an earlier rule.
+#[derive(Debug, Clone)]
+pub struct Shadowed {
+    pub rule: usize,
+    // The earlier rules that match some of this rule's strings.
+    pub by: Vec<usize>,
+}
+
+// Two rules that match some of the same strings.  `first` comes before `second`, so it wins on `example`.
+#[derive(Debug, Clone)]
+pub struct Overlap {
+    pub first: usize,
+    pub second: usize,
+    pub example: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShadowReport {
+    pub shadowed: Vec<Shadowed>,
+    pub overlaps: Vec<Overlap>,
+}
+
+pub fn from_file(file_path: &std::path::Path) -> Result<Vec<Rule>, Box<dyn std::error::Error>> {
+    let file = BufReader::new(File::open(file_path)?);
+    let mut rules = Vec::new();
+
+    for (line_count, line) in file.lines().map_while(Result::ok).enumerate() {
+        let line = line.trim();
+        if line.starts_with("//") || line.is_empty() {
+            continue;
+        }
+        let (name, regex) = match line.split_once(char::is_whitespace) {
+            Some((name, regex)) => (name, regex.trim_start()),
+            None => {
+                return Err(format!("rule at line {} has no regex", line_count + 1).into());
+            }
+        };
+        let nfa = regex_parser::parse_to_nfa(regex).ok_or_else(|| {
+            format!(
+                "failed to parse the regex of {} at line {}",
+                name,
+                line_count + 1
+            )
+        })?;
+        rules.push(Rule {
+            name: name.to_string(),
+            regex: regex.to_string(),
+            nfa,
+        });
+    }
+    Ok(rules)
+}
+
+// Checks each rule against the union of the rules before it, and each pair of rules for a common string.
+pub fn analyze(rules: &[Rule]) -> ShadowReport {
+    let mut shadowed = Vec::new();
+    let mut overlaps = Vec::new();
+    // The union of all the rules seen so far, kept minimal so it does not grow with every product.
+    let mut earlier: Option<FA> = None;
+
+    for (i, rule) in rules.iter().enumerate() {
+        let mut by = Vec::new();
+        for (j, other) in rules[..i].iter().enumerate() {
+            if let Some(example) = other.nfa.intersection(&rule.nfa).shortest_accepted() {
+                by.push(j);
+                overlaps.push(Overlap {
+                    first: j,
+                    second: i,
+                    example,
+                });
+            }
+        }
+
+        let covered = match &earlier {
+            Some(union) => rule.nfa.is_subset_of(union),
+            None => rule.nfa.shortest_accepted().is_none(),
+        };
+        if covered {
+            shadowed.push(Shadowed { rule: i, by });
+        }
+
+        let mut union = match earlier {
+            Some(union) => union.union_product(&rule.nfa),
+            None => rule.nfa.to_dfa(),
+        };
+        union.minimize();
+        earlier = Some(union);
+    }
+
+    ShadowReport { shadowed, overlaps }
+}
+
+// Writes the report out with rule names, for the rules it was computed from.
+pub fn describe(rules: &[Rule], report: &ShadowReport) -> Result<String, std::fmt::Error> {
+    let mut output = String::new();
+
+    for shadow in &report.shadowed {
+        let rule = &rules[shadow.rule];
+        if shadow.by.is_empty() {
+            writeln!(output, "{} can never win: it matches nothing", rule.name)?;
+        } else {
+            let names = shadow
+                .by
+                .iter()
+                .map(|&j| rules[j].name.as_str())
+                .collect::<Vec<&str>>();
+            writeln!(
+                output,
+                "{} can never win: every string it matches is matched first by {}",
+                rule.name,
+                names.join(", ")
+            )?;
+        }
+    }
+    for o in &report.overlaps {
+        let (first, second) = (&rules[o.first].name, &rules[o.second].name);
+        writeln!(
+            output,
+            "{} and {} overlap: both match {:?}, which goes to {}",
+            first, second, o.example, first
+        )?;
+    }
+    if report.shadowed.is_empty() && report.overlaps.is_empty() {
+        writeln!(output, "no rules overlap")?;
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(spec: &[(&str, &str)]) -> Vec<Rule> {
+        spec.iter()
+            .map(|&(name, regex)| Rule {
+                name: name.to_string(),
+                regex: regex.to_string(),
+                nfa: regex_parser::parse_to_nfa(regex).unwrap(),
+            })
+            .collect()
+    }
+
+    // On a string both rules match in full, the earlier rule wins, so the overlap is reported in rule order.
+    #[test]
+    fn earlier_rule_wins_equal_length_matches() {
+        let rules = rules(&[("IF", "if"), ("IDENT", "[a-z]+")]);
+        let report = analyze(&rules);
+        assert!(report.shadowed.is_empty());
+        assert_eq!(report.overlaps.len(), 1);
+        let overlap = &report.overlaps[0];
+        assert_eq!((overlap.first, overlap.second), (0, 1));
+        assert_eq!(overlap.example, "if");
+        assert_eq!(
+            describe(&rules, &report).unwrap(),
+            "IF and IDENT overlap: both match \"if\", which goes to IF\n"
+        );
+    }
+
+    #[test]
+    fn keyword_after_identifier_is_shadowed() {
+        let rules = rules(&[("IDENT", "[a-z]+"), ("NUM", "[0-9]+"), ("IF", "if")]);
+        let report = analyze(&rules);
+        assert_eq!(report.shadowed.len(), 1);
+        assert_eq!(report.shadowed[0].rule, 2);
+        assert_eq!(report.shadowed[0].by, vec![0]);
+        assert!(describe(&rules, &report)
+            .unwrap()
+            .starts_with("IF can never win: every string it matches is matched first by IDENT\n"));
+    }
+
+    // Tokens are matched longest first, so a rule that only matches longer strings than an earlier one still wins on
+    // them, even though the earlier rule matches a prefix.
+    #[test]
+    fn longest_match_wins_over_priority() {
+        let rules = rules(&[
+            ("INT", "[0-9]+"),
+            ("FLOAT", "[0-9]+\\.[0-9]+"),
+            ("NONE", "a&b"),
+        ]);
+        let report = analyze(&rules);
+        assert!(report.overlaps.is_empty());
+        let shadowed = report
+            .shadowed
+            .iter()
+            .map(|s| s.rule)
+            .collect::<Vec<usize>>();
+        assert_eq!(shadowed, vec![2]);
+        assert!(describe(&rules, &report)
+            .unwrap()
+            .starts_with("NONE can never win: it matches nothing\n"));
+    }
+}
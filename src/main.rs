@@ -1,14 +1,39 @@
 #![allow(dead_code)]
 // mod state_set;
+mod analysis;
+mod boolean_ops;
+mod captures;
+mod closures;
+mod coverage;
+mod derivatives;
+mod diff;
+mod enumerate;
+mod equivalence;
 mod fa;
 mod fa_drawer;
 mod fa_reader;
+mod followpos;
+mod glushkov;
+mod hopcroft;
+mod lexer_rules;
+mod partition_trace;
 mod regex_parser;
+mod regex_set;
+mod regular_ops;
+mod sample;
+mod search;
+mod simplify;
+mod state_elimination;
+mod subset_trace;
 mod symbol;
 mod thompsons;
 mod transition;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use equivalence::Equivalence;
+use fa::FA;
+use regex_parser::Construction;
+use symbol::ASCII;
 use std::path::Path;
 use std::fs::File;
 use std::io::prelude::*;
@@ -20,6 +45,481 @@ use std::io::prelude::*;
 //  * Though granted, the Kleene plus is just Kleene star (in Thompson's) without the epsilon transition between start and end.  I'm sure the DFA factors all that out anyway.
 //  * And the question mark is just Kleene star without the looping backwards epsilon transition from the end of the inner piece to its start.  This is of course simpler than converting X? into (X|eps).
 
+// Subcommands take their automata as operands: a path to a `.fa` specification file, or else a regex, which is built
+// with the construction given by `--construction`.
+fn load_fa(args: &ArgMatches, operand: &str) -> Result<FA, Box<dyn std::error::Error>> {
+    let path = Path::new(operand);
+    if path.extension().is_some_and(|ext| ext == "fa") && path.is_file() {
+        fa_reader::from_file(path)
+    } else {
+        Ok(regex_parser::parse_with(operand, construction(args)?)?)
+    }
+}
+
+fn construction(args: &ArgMatches) -> Result<Construction, Box<dyn std::error::Error>> {
+    match args.value_of("construction") {
+        Some(name) => Ok(name.parse()?),
+        None => Ok(Construction::Thompson),
+    }
+}
+
+fn equiv(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let left = load_fa(args, args.value_of("left").unwrap())?;
+    let right = load_fa(args, args.value_of("right").unwrap())?;
+
+    let result = equivalence::equivalence(&left, &right);
+    println!("{}", result);
+    // A nonzero exit status lets scripts use this as a check.
+    if let Equivalence::Distinct { .. } = result {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn write_graphviz(fa: FA, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
+    write_dotfile(&fa_drawer::draw_fa(fa)?, filepath)
+}
+
+// Writes out a drawing made by one of the `fa_drawer` functions.
+fn write_dotfile(dotfile: &str, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = File::create(filepath)?;
+    file.write_all(dotfile.as_bytes())?;
+    Ok(())
+}
+
+// Displays an automaton and draws it if asked to, labelling the states of a determinized one with their NFA subsets
+// when `--subsets` is given.
+fn show(args: &ArgMatches, fa: FA) -> Result<(), Box<dyn std::error::Error>> {
+    let subsets = args.is_present("subsets");
+    if subsets {
+        println!("{:#}", fa);
+    } else {
+        println!("{}", fa);
+    }
+
+    if let Some(filepath) = args.value_of("output-graphviz") {
+        let dotfile = if subsets {
+            fa_drawer::draw_fa_with_subsets(&fa)?
+        } else {
+            fa_drawer::draw_fa(fa)?
+        };
+        write_dotfile(&dotfile, filepath)?;
+    }
+    Ok(())
+}
+
+fn diff(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let mut old = load_fa(args, args.value_of("old").unwrap())?.to_dfa();
+    let mut new = load_fa(args, args.value_of("new").unwrap())?.to_dfa();
+    if !args.is_present("as-is") {
+        old.minimize();
+        new.minimize();
+    }
+
+    let diff = diff::diff(&old, &new);
+    print!("{}", diff::describe(&diff)?);
+
+    if let Some(filepath) = args.value_of("output-graphviz") {
+        write_dotfile(&fa_drawer::draw_diff(&old, &new, &diff)?, filepath)?;
+    }
+    // As with equiv, a nonzero exit status lets scripts use this as a check.
+    if !diff.is_isomorphism() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn combine(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let left = load_fa(args, args.value_of("left").unwrap())?;
+    let right = args
+        .value_of("right")
+        .map(|right| load_fa(args, right))
+        .transpose()?;
+
+    let operation = args.value_of("operation").unwrap();
+    let mut fa = match (operation, right) {
+        ("complement", None) => {
+            let alphabet = match args.value_of("alphabet") {
+                Some(chars) => chars.chars().collect::<Vec<char>>(),
+                None => ASCII.to_vec(),
+            };
+            left.complement(&alphabet)
+        }
+        ("star", None) => left.star(),
+        ("plus", None) => left.plus(),
+        ("optional", None) => left.optional(),
+        ("complement" | "star" | "plus" | "optional", Some(_)) => {
+            return Err(format!("{} takes a single automaton", operation).into())
+        }
+        (_, None) => return Err("this operation takes two automata".into()),
+        ("intersection", Some(right)) => left.intersection(&right),
+        ("union", Some(right)) => left.union_product(&right),
+        ("difference", Some(right)) => left.difference(&right),
+        ("concat", Some(right)) => left.concat(&right),
+        _ => unreachable!(),
+    };
+    fa.minimize();
+    println!("{}", fa);
+
+    if let Some(filepath) = args.value_of("output-graphviz") {
+        write_graphviz(fa, filepath)?;
+    }
+    Ok(())
+}
+
+// Unlike `combine`, shows the automaton the operation gives without minimizing it.
+fn transform(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let mut fa = load_fa(args, args.value_of("automaton").unwrap())?;
+
+    match args.value_of("operation").unwrap() {
+        "remove-epsilons" => fa = fa.remove_epsilons(),
+        "trim" => fa.trim(),
+        "reverse" => fa = fa.reverse(),
+        "prefixes" => fa = fa.prefixes(),
+        "suffixes" => fa = fa.suffixes(),
+        "factors" => fa = fa.factors(),
+        "determinize" => fa = fa.dfa_from(),
+        _ => unreachable!(),
+    }
+    show(args, fa)
+}
+
+fn shadow(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let rules = lexer_rules::from_file(Path::new(args.value_of("rules").unwrap()))?;
+
+    let report = lexer_rules::analyze(&rules);
+    print!("{}", lexer_rules::describe(&rules, &report)?);
+    if !report.shadowed.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn stats(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let fa = load_fa(args, args.value_of("automaton").unwrap())?;
+    let mut language = analysis::Language::new(&fa);
+
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    println!("empty: {}", yes_no(language.is_empty()));
+    println!("finite: {}", yes_no(language.is_finite()));
+    match language.count() {
+        Some(count) => println!("strings: {}", count),
+        None => println!("strings: infinitely many"),
+    }
+    if let Some(shortest) = language.dfa().shortest_length() {
+        println!("shortest: {}", shortest);
+    }
+    let longest = language.longest_length();
+    if let Some(longest) = longest {
+        println!("longest: {}", longest);
+    }
+
+    // Finite languages are counted out to their longest string, unless told otherwise.
+    let up_to = match args.value_of("up-to") {
+        Some(n) => n.parse::<usize>()?,
+        None if language.is_finite() => longest.unwrap_or(0),
+        None => 10,
+    };
+    for (n, count) in language.counts_up_to(up_to).into_iter().enumerate() {
+        println!("length {}: {}", n, count);
+    }
+    Ok(())
+}
+
+fn enumerate(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let fa = load_fa(args, args.value_of("automaton").unwrap())?;
+    let max_len = args.value_of("max-len").map(str::parse::<usize>).transpose()?;
+    // Without either limit an infinite language would print forever.
+    let count = match args.value_of("count") {
+        Some(n) => Some(n.parse::<usize>()?),
+        None if max_len.is_none() => Some(20),
+        None => None,
+    };
+
+    for string in fa.enumerate(max_len).take(count.unwrap_or(usize::MAX)) {
+        println!("{:?}", string);
+    }
+    Ok(())
+}
+
+fn sample(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let fa = load_fa(args, args.value_of("automaton").unwrap())?;
+    let length = args.value_of("length").unwrap().parse::<usize>()?;
+    let count = args.value_of("count").unwrap_or("1").parse::<usize>()?;
+    let seed = match args.value_of("seed") {
+        Some(seed) => seed.parse::<u64>()?,
+        None => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_nanos() as u64,
+    };
+    let mut rng = sample::Rng::new(seed);
+    let mut sampler = sample::Sampler::new(&fa);
+    // Printed to stderr so the samples on stdout stay clean, but a failing input can still be reproduced.
+    eprintln!(
+        "seed: {} ({} accepted strings of length {})",
+        seed,
+        sampler.count(length),
+        length
+    );
+    for _ in 0..count {
+        let string = if args.is_present("rejected") {
+            sampler.rejected_near(length, &mut rng)
+        } else {
+            sampler.accepted(length, &mut rng)
+        };
+        match string {
+            Some(string) => println!("{:?}", string),
+            None => return Err(format!("no strings to sample at length {}", length).into()),
+        }
+    }
+    Ok(())
+}
+
+fn coverage(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let fa = load_fa(args, args.value_of("automaton").unwrap())?;
+
+    if let Some(file) = args.value_of("check") {
+        let expectations = coverage::read(&std::fs::read_to_string(file)?)?;
+        let failures = coverage::check(&fa, &expectations);
+        for f in &failures {
+            let expected = if f.accept { "accepted" } else { "rejected" };
+            println!("{:?} should be {}", f.input, expected);
+        }
+        println!(
+            "{} of {} expectations met",
+            expectations.len() - failures.len(),
+            expectations.len()
+        );
+        if !failures.is_empty() {
+            std::process::exit(1);
+        }
+    } else {
+        print!("{}", coverage::write(&coverage::suite(&fa))?);
+    }
+    Ok(())
+}
+
+fn to_regex(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let mut fa = load_fa(args, args.value_of("automaton").unwrap())?;
+    if args.is_present("minimize") {
+        fa.minimize();
+    }
+
+    match fa.to_regex() {
+        Some(regex) => println!("{}", regex),
+        None => println!("the automaton accepts nothing"),
+    }
+    Ok(())
+}
+
+fn followpos(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let regex = args.value_of("regex").unwrap();
+    let expr = regex_parser::parse_to_expr(regex)
+        .ok_or_else(|| format!("failed to parse: {}", regex))?;
+
+    let positions = followpos::Positions::new(&expr)?;
+    print!("{}", positions);
+    let fa = positions.dfa();
+    println!("{}", fa);
+
+    if let Some(filepath) = args.value_of("output-graphviz") {
+        write_graphviz(fa, filepath)?;
+    }
+    Ok(())
+}
+
+// Prints the Thompson NFA of a regex, and draws it with each operator's piece in a cluster of its own.
+fn thompson(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let regex = args.value_of("regex").unwrap();
+    let expr = regex_parser::parse_to_expr(regex)
+        .ok_or_else(|| format!("failed to parse: {}", regex))?;
+
+    let (fa, fragments) = thompsons::expr_to_nfa_with_fragments(expr);
+    println!("{}", fa);
+
+    if let Some(filepath) = args.value_of("output-graphviz") {
+        write_dotfile(&fa_drawer::draw_thompson(&fa, &fragments)?, filepath)?;
+    }
+    Ok(())
+}
+
+// Matches strings by simulating the Glushkov automaton with bitsets, without building a DFA.
+fn match_strings(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let regex = args.value_of("regex").unwrap();
+    let expr = regex_parser::parse_to_expr(regex)
+        .ok_or_else(|| format!("failed to parse: {}", regex))?;
+    let matcher = glushkov::BitMatcher::new(&followpos::Positions::new(&expr)?);
+
+    for input in args.values_of("input").into_iter().flatten() {
+        let verdict = if matcher.is_match(input) { "accepted" } else { "rejected" };
+        println!("{:?}: {}", input, verdict);
+    }
+    Ok(())
+}
+
+// Prints the matches on each line of a file, or of stdin, as `line:start..end: text` with byte offsets.
+fn find(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let fa = load_fa(args, args.value_of("pattern").unwrap())?;
+    let kind = if args.is_present("longest") {
+        search::MatchKind::LeftmostLongest
+    } else {
+        search::MatchKind::LeftmostFirst
+    };
+    let searcher = search::Searcher::new(&fa, kind);
+    let text = match args.value_of("file") {
+        Some(file) => std::fs::read_to_string(file)?,
+        None => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            text
+        }
+    };
+
+    for (number, line) in text.lines().enumerate() {
+        let found = if args.is_present("all") {
+            searcher.find_iter(line).collect()
+        } else {
+            searcher.find(line).into_iter().collect::<Vec<_>>()
+        };
+        for range in found {
+            println!(
+                "{}:{}..{}: {:?}",
+                number + 1,
+                range.start,
+                range.end,
+                &line[range.clone()]
+            );
+        }
+    }
+    Ok(())
+}
+
+// Prints the span and text of each group of the leftmost-first match in each input.
+fn captures(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let regex = args.value_of("regex").unwrap();
+    let expr = regex_parser::parse_to_expr(regex)
+        .ok_or_else(|| format!("failed to parse: {}", regex))?;
+    let vm = captures::PikeVM::new(&expr)?;
+
+    for input in args.values_of("input").into_iter().flatten() {
+        let caps = match vm.captures(input) {
+            Some(caps) => caps,
+            None => {
+                println!("{:?}: no match", input);
+                continue;
+            }
+        };
+        println!("{:?}:", input);
+        for (i, (name, span)) in caps.iter().enumerate() {
+            let label = match name {
+                Some(name) => format!("{} <{}>", i, name),
+                None => i.to_string(),
+            };
+            match span {
+                Some(span) => println!("  {}: {}..{} {:?}", label, span.start, span.end, &input[span.clone()]),
+                None => println!("  {}: unset", label),
+            }
+        }
+    }
+    Ok(())
+}
+
+// Reads inputs from stdin, one per line, and prints the ids of the patterns that match each of them.
+fn match_set(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let patterns = args
+        .values_of("pattern")
+        .into_iter()
+        .flatten()
+        .map(|pattern| load_fa(args, pattern))
+        .collect::<Result<Vec<FA>, _>>()?;
+    let set = regex_set::RegexSet::new(&patterns);
+    let ids = |ids: std::collections::BTreeSet<usize>| {
+        ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(", ")
+    };
+
+    if args.is_present("states") {
+        let mut states = set.dfa().states().clone();
+        states.sort_unstable();
+        for state in states {
+            if set.dfa().is_accepting(state) {
+                println!("state {} accepts for {}", state, ids(set.patterns_of(state)));
+            }
+        }
+    }
+    if let Some(filepath) = args.value_of("output-graphviz") {
+        write_graphviz(set.dfa().clone(), filepath)?;
+    }
+
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+    for line in text.lines() {
+        let matched = set.matches(line);
+        if matched.is_empty() {
+            println!("{:?}: none", line);
+        } else {
+            println!("{:?}: {}", line, ids(matched));
+        }
+    }
+    Ok(())
+}
+
+// Prints how an algorithm runs on an automaton, step by step.
+fn trace(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let fa = load_fa(args, args.value_of("automaton").unwrap())?;
+    let markdown = args.is_present("markdown");
+
+    match args.value_of("algorithm").unwrap() {
+        "subsets" => {
+            let trace = fa.subset_trace();
+            print!("{}", if markdown { trace.markdown()? } else { trace.text()? });
+        }
+        "minimize" => {
+            let trace = fa.partition_trace();
+            print!("{}", if markdown { trace.markdown()? } else { trace.text()? });
+            if let Some(filepath) = args.value_of("output-graphviz") {
+                write_dotfile(&fa_drawer::draw_partition(&trace)?, filepath)?;
+            }
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+fn compare(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let regex = args.value_of("regex").unwrap();
+    let nfa = regex_parser::parse_with(regex, Construction::Thompson)?;
+    let mut minimal = nfa.clone();
+    minimal.minimize();
+    println!("thompson nfa: {} states", nfa.num_states());
+    println!("subset construction dfa: {} states", nfa.dfa_from().num_states());
+    match regex_parser::parse_with(regex, Construction::Glushkov) {
+        Ok(fa) => {
+            println!("glushkov nfa: {} states", fa.num_states());
+            println!("subset construction dfa from glushkov: {} states", fa.dfa_from().num_states());
+        }
+        Err(e) => println!("glushkov nfa: {}", e),
+    }
+    for (name, construction) in [
+        ("derivatives", Construction::Derivatives),
+        ("followpos", Construction::Followpos),
+    ] {
+        match regex_parser::parse_with(regex, construction) {
+            Ok(fa) => println!("{} dfa: {} states", name, fa.num_states()),
+            Err(e) => println!("{} dfa: {}", name, e),
+        }
+    }
+    println!("minimal dfa: {} states", minimal.num_states());
+    Ok(())
+}
+
+fn simplify(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let regex = args.value_of("regex").unwrap();
+    let expr = regex_parser::parse_to_expr(regex)
+        .ok_or_else(|| format!("failed to parse: {}", regex))?;
+    println!("{}", simplify::simplify(expr));
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let matches = App::new("regex-visualizer")
         .author("Spencer G. <swyverng55@g.ucla.edu>")
@@ -44,10 +544,290 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .long("graphviz")
                 .value_name("OUTPUT")
                 .help("Output a .gv file which displays your finite automaton.")
-                .takes_value(true),
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("construction")
+                .long("construction")
+                .value_name("METHOD")
+                .possible_values(Construction::NAMES)
+                .help("How subcommands build automata from regexes.  Defaults to thompson.")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("subsets")
+                .long("subsets")
+                .help("Label each state of a determinized automaton with the set of NFA states it stands for.")
+                .global(true),
+        )
+        .subcommand(
+            SubCommand::with_name("equiv")
+                .about("Checks whether two automata accept the same language, giving a shortest string they disagree on if not.")
+                .arg(Arg::with_name("left").help("A regex or .fa file.").required(true))
+                .arg(Arg::with_name("right").help("A regex or .fa file.").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Shows how the minimal DFA of one automaton differs from another's, state by state.")
+                .arg(Arg::with_name("old").help("A regex or .fa file.").required(true))
+                .arg(Arg::with_name("new").help("A regex or .fa file.").required(true))
+                .arg(
+                    Arg::with_name("as-is")
+                        .long("as-is")
+                        .help("Compare the automata as they are, only determinizing them, instead of their minimal DFAs."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("combine")
+                .about("Combines the languages of automata and displays the minimal DFA of the result.")
+                .arg(
+                    Arg::with_name("operation")
+                        .possible_values(&[
+                            "intersection",
+                            "union",
+                            "difference",
+                            "complement",
+                            "concat",
+                            "star",
+                            "plus",
+                            "optional",
+                        ])
+                        .required(true),
+                )
+                .arg(Arg::with_name("left").help("A regex or .fa file.").required(true))
+                .arg(Arg::with_name("right").help("A regex or .fa file; not used by complement and the repetitions."))
+                .arg(
+                    Arg::with_name("alphabet")
+                        .short("a")
+                        .long("alphabet")
+                        .value_name("CHARS")
+                        .help("The alphabet to complement over.  Defaults to printable ASCII.")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("transform")
+                .about("Transforms an automaton and displays the result without minimizing it.")
+                .arg(
+                    Arg::with_name("operation")
+                        .possible_values(&[
+                            "remove-epsilons",
+                            "trim",
+                            "reverse",
+                            "prefixes",
+                            "suffixes",
+                            "factors",
+                            "determinize",
+                        ])
+                        .required(true),
+                )
+                .arg(Arg::with_name("automaton").help("A regex or .fa file.").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("shadow")
+                .about("Reports lexer rules that can never win, and pairs of rules that match a common string.")
+                .arg(
+                    Arg::with_name("rules")
+                        .help("A file with one rule per line: a name, then a regex.")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Reports whether a language is empty or finite, and how many strings of each length it has.")
+                .arg(Arg::with_name("automaton").help("A regex or .fa file.").required(true))
+                .arg(
+                    Arg::with_name("up-to")
+                        .short("n")
+                        .long("up-to")
+                        .value_name("LENGTH")
+                        .help("Count strings of each length up to this one.")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("enumerate")
+                .about("Lists the strings an automaton accepts, shortest first and then alphabetically.")
+                .arg(Arg::with_name("automaton").help("A regex or .fa file.").required(true))
+                .arg(
+                    Arg::with_name("max-len")
+                        .short("l")
+                        .long("max-len")
+                        .value_name("LENGTH")
+                        .help("Stop after the strings of this length.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .short("c")
+                        .long("count")
+                        .value_name("COUNT")
+                        .help("Stop after this many strings.  Defaults to 20 if no maximum length is given.")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sample")
+                .about("Draws accepted strings of a given length uniformly at random.")
+                .arg(Arg::with_name("automaton").help("A regex or .fa file.").required(true))
+                .arg(
+                    Arg::with_name("length")
+                        .short("n")
+                        .long("length")
+                        .value_name("LENGTH")
+                        .help("The length of the strings to draw.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .short("c")
+                        .long("count")
+                        .value_name("COUNT")
+                        .help("How many strings to draw.  Defaults to 1.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .value_name("SEED")
+                        .help("Seeds the generator.  Defaults to the clock; the seed used is printed to stderr.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("rejected")
+                        .short("r")
+                        .long("rejected")
+                        .help("Draw rejected strings one edit away from accepted ones instead."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("coverage")
+                .about("Generates accept/reject test inputs that exercise every reachable transition and accepting state of the automaton.")
+                .arg(Arg::with_name("automaton").help("A regex or .fa file.").required(true))
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .value_name("FILE")
+                        .help("Check the automaton against previously generated expectations instead.")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("to-regex")
+                .about("Converts an automaton back into a regex by state elimination.")
+                .arg(Arg::with_name("automaton").help("A regex or .fa file.").required(true))
+                .arg(
+                    Arg::with_name("minimize")
+                        .short("m")
+                        .long("minimize")
+                        .help("Minimize the automaton first, which usually gives a shorter regex."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("followpos")
+                .about("Prints the followpos table of a regex and the DFA built from it.")
+                .arg(Arg::with_name("regex").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("thompson")
+                .about("Prints the Thompson NFA of a regex; its drawing boxes each subexpression's piece.")
+                .arg(Arg::with_name("regex").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("match")
+                .about("Matches strings against a regex by bit-parallel simulation of its Glushkov automaton.")
+                .arg(Arg::with_name("regex").required(true))
+                .arg(Arg::with_name("input").multiple(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("find")
+                .about("Finds matches of a regex or .fa automaton anywhere in each line of a file, or of stdin.")
+                .arg(Arg::with_name("pattern").required(true))
+                .arg(Arg::with_name("file"))
+                .arg(
+                    Arg::with_name("longest")
+                        .short("l")
+                        .long("longest")
+                        .help("Take the longest match at the leftmost start, instead of the first by priority."),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .short("a")
+                        .long("all")
+                        .help("Print every non-overlapping match, not just the leftmost."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("captures")
+                .about("Prints what each group of a regex matched in the leftmost-first match in each input.")
+                .arg(Arg::with_name("regex").required(true))
+                .arg(Arg::with_name("input").multiple(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("set")
+                .about("Matches each line of stdin against many patterns at once, printing which of them match.")
+                .arg(Arg::with_name("pattern").required(true).multiple(true))
+                .arg(
+                    Arg::with_name("states")
+                        .short("s")
+                        .long("states")
+                        .help("First list the patterns each accepting state of the combined DFA accepts for."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("trace")
+                .about("Shows an algorithm running on an automaton step by step.")
+                .arg(
+                    Arg::with_name("algorithm")
+                        .possible_values(&["subsets", "minimize"])
+                        .required(true),
+                )
+                .arg(Arg::with_name("automaton").help("A regex or .fa file.").required(true))
+                .arg(
+                    Arg::with_name("markdown")
+                        .short("m")
+                        .long("markdown")
+                        .help("Write the trace as markdown instead of plain text."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("compare")
+                .about("Compares the number of states each construction builds for a regex.")
+                .arg(Arg::with_name("regex").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("simplify")
+                .about("Rewrites a regex into a smaller one for the same language.")
+                .arg(Arg::with_name("regex").required(true)),
         )
         .get_matches();
 
+    match matches.subcommand() {
+        ("equiv", Some(args)) => return equiv(args),
+        ("combine", Some(args)) => return combine(args),
+        ("diff", Some(args)) => return diff(args),
+        ("transform", Some(args)) => return transform(args),
+        ("shadow", Some(args)) => return shadow(args),
+        ("stats", Some(args)) => return stats(args),
+        ("enumerate", Some(args)) => return enumerate(args),
+        ("sample", Some(args)) => return sample(args),
+        ("coverage", Some(args)) => return coverage(args),
+        ("to-regex", Some(args)) => return to_regex(args),
+        ("simplify", Some(args)) => return simplify(args),
+        ("compare", Some(args)) => return compare(args),
+        ("followpos", Some(args)) => return followpos(args),
+        ("thompson", Some(args)) => return thompson(args),
+        ("match", Some(args)) => return match_strings(args),
+        ("find", Some(args)) => return find(args),
+        ("captures", Some(args)) => return captures(args),
+        ("set", Some(args)) => return match_set(args),
+        ("trace", Some(args)) => return trace(args),
+        _ => {}
+    }
+
     // * right now, specifying an FA doesn't do anything useful.
     if matches.is_present("specify") {
         if let Some(file) = matches.value_of("input-file") {
@@ -70,13 +850,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let file_path = Path::new(&file);
             let input = std::fs::read_to_string(file_path)?;
             if let Some(fa) = regex_parser::parse_to_dfa(&input) {
-                println!("{}", fa);
-
-                if let Some(filepath) = matches.value_of("output-graphviz") {
-                    let dotfile = fa_drawer::draw_fa(fa)?;
-                    let mut file = File::create(filepath)?;
-                    file.write_all(dotfile.as_bytes())?;
-                }
+                show(&matches, fa)?;
             } else {
                 println!("failed to parse:\n{}", input);
             }
@@ -88,17 +862,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let input = input.trim().to_string();
 
             if let Some(fa) = regex_parser::parse_to_dfa(&input) {
-                println!("{}", fa);
+                show(&matches, fa.clone())?;
                 let mut input = String::new();
                 std::io::stdin().read_line(&mut input)?;
                 let input = input.trim().to_string();
                 println!("{}", fa.dfa_accepts(input));
-
-                if let Some(filepath) = matches.value_of("output-graphviz") {
-                    let dotfile = fa_drawer::draw_fa(fa)?;
-                    let mut file = File::create(filepath)?;
-                    file.write_all(dotfile.as_bytes())?;
-                }
             } else {
                 println!("failed to parse:\n{}", input);
             }
@@ -0,0 +1,225 @@
+// A record of Hopcroft's partition refinement, as `FA::minimize` runs it.  The states start out split into accepting
+// and non-accepting blocks.  Then splitters, each a block and a symbol, come off a waiting list: a block with some
+// states that move into the splitter on the symbol and some that do not is split in two.  Once no splitter splits
+// anything, the states left in a block accept exactly the same strings, and each block is a state of the minimal DFA.
+//
+// Refinement needs a complete DFA, so a dead state, written `dead`, stands in for the missing transitions.
+
+use crate::fa::{State, FA};
+use crate::hopcroft::Round;
+
+use std::fmt::{self, Write};
+
+#[derive(Debug, Clone)]
+pub struct PartitionTrace {
+    // The DFA that was refined.
+    pub dfa: FA,
+    pub alphabet: Vec<char>,
+    // The DFA state behind each index of the refinement.  The index past the end is the dead state.
+    pub states: Vec<State>,
+    pub initial: Vec<Vec<usize>>,
+    pub rounds: Vec<Round>,
+    // The block each index ends up in.
+    pub block_of: Vec<usize>,
+}
+
+impl PartitionTrace {
+    fn member(&self, q: usize) -> String {
+        match self.states.get(q) {
+            Some(state) => state.to_string(),
+            None => "dead".to_string(),
+        }
+    }
+    // A block as `{0,2,dead}`, in order of state, with the dead state last.
+    fn block(&self, block: &[usize]) -> String {
+        let mut block = block.to_vec();
+        block.sort_by_key(|&q| self.states.get(q).copied().unwrap_or(State::MAX));
+        let members = block
+            .iter()
+            .map(|&q| self.member(q))
+            .collect::<Vec<String>>();
+        format!("{{{}}}", members.join(","))
+    }
+    fn partition(&self, blocks: &[Vec<usize>]) -> String {
+        blocks
+            .iter()
+            .map(|block| self.block(block))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+    // The same as `partition`, with each block in a markdown code span.
+    fn partition_code(&self, blocks: &[Vec<usize>]) -> String {
+        blocks
+            .iter()
+            .map(|block| format!("`{}`", self.block(block)))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+    // The blocks at the end, in order of their smallest state, with the dead state's block last.
+    fn final_blocks(&self) -> Vec<Vec<usize>> {
+        let mut blocks: Vec<Vec<usize>> = Vec::new();
+        let mut position = std::collections::HashMap::new();
+        let mut order = (0..self.block_of.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|&q| self.states.get(q).copied().unwrap_or(State::MAX));
+        for q in order {
+            let i = *position.entry(self.block_of[q]).or_insert_with(|| {
+                blocks.push(Vec::new());
+                blocks.len() - 1
+            });
+            blocks[i].push(q);
+        }
+        let dead = self.states.len();
+        blocks.sort_by_key(|block| block.contains(&dead));
+        blocks
+    }
+    // The DFA states grouped into their equivalence classes, in order, leaving out the dead state.  The states that
+    // are equivalent to the dead state, if any, make up the last class.
+    pub fn classes(&self) -> Vec<Vec<State>> {
+        self.final_blocks()
+            .into_iter()
+            .map(|block| {
+                block
+                    .iter()
+                    .filter_map(|&q| self.states.get(q).copied())
+                    .collect()
+            })
+            .filter(|class: &Vec<State>| !class.is_empty())
+            .collect()
+    }
+    // Whether a DFA state ends up equivalent to the dead state, so that no string takes it to acceptance.
+    pub fn is_dead(&self, state: State) -> bool {
+        let dead = self.states.len();
+        self.states
+            .iter()
+            .position(|&s| s == state)
+            .is_some_and(|q| self.block_of[q] == self.block_of[dead])
+    }
+    // The starting partition, then each round that split a block: its splitter, the splits, and the partition after.
+    pub fn text(&self) -> Result<String, fmt::Error> {
+        let mut output = String::new();
+
+        writeln!(output, "initial: {}", self.partition(&self.initial))?;
+        for (i, round) in self.rounds.iter().enumerate() {
+            writeln!(
+                output,
+                "round {}: splitter {} on {:?}",
+                i + 1,
+                self.block(&round.splitter),
+                self.alphabet[round.symbol]
+            )?;
+            for (inside, outside) in &round.splits {
+                let whole = inside
+                    .iter()
+                    .chain(outside)
+                    .copied()
+                    .collect::<Vec<usize>>();
+                writeln!(
+                    output,
+                    "  {} splits into {} and {}",
+                    self.block(&whole),
+                    self.block(inside),
+                    self.block(outside)
+                )?;
+            }
+            writeln!(output, "  partition: {}", self.partition(&round.blocks))?;
+        }
+
+        let blocks = self.final_blocks();
+        writeln!(output, "final: {}", self.partition(&blocks))?;
+        // The dead state's block is not part of the minimal DFA, and takes the states equivalent to it along.  The
+        // minimal DFA keeps a starting state even when that leaves nothing.
+        writeln!(
+            output,
+            "{} states in the minimal DFA",
+            (blocks.len() - 1).max(1)
+        )?;
+
+        Ok(output)
+    }
+    // The same as `text`, as a markdown list of rounds.
+    pub fn markdown(&self) -> Result<String, fmt::Error> {
+        let mut output = String::new();
+
+        writeln!(output, "Initial: {}", self.partition_code(&self.initial))?;
+        writeln!(output)?;
+        for (i, round) in self.rounds.iter().enumerate() {
+            writeln!(
+                output,
+                "{}. Splitter `{}` on {}",
+                i + 1,
+                self.block(&round.splitter),
+                code(self.alphabet[round.symbol])
+            )?;
+            for (inside, outside) in &round.splits {
+                let whole = inside
+                    .iter()
+                    .chain(outside)
+                    .copied()
+                    .collect::<Vec<usize>>();
+                writeln!(
+                    output,
+                    "   - `{}` splits into `{}` and `{}`",
+                    self.block(&whole),
+                    self.block(inside),
+                    self.block(outside)
+                )?;
+            }
+            writeln!(
+                output,
+                "   - Partition: {}",
+                self.partition_code(&round.blocks)
+            )?;
+        }
+        if !self.rounds.is_empty() {
+            writeln!(output)?;
+        }
+
+        let blocks = self.final_blocks();
+        writeln!(output, "Final: {}", self.partition_code(&blocks))?;
+        writeln!(output)?;
+        writeln!(
+            output,
+            "{} states in the minimal DFA.",
+            (blocks.len() - 1).max(1)
+        )?;
+
+        Ok(output)
+    }
+}
+
+// A character in a markdown code span.
+fn code(c: char) -> String {
+    match c {
+        '`' => "`` ` ``".to_string(),
+        c => format!("`{}`", c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex_parser::parse_to_nfa;
+
+    #[test]
+    fn markdown_follows_the_text() {
+        let trace = parse_to_nfa("(a|b)*abb").unwrap().partition_trace();
+        let text = trace.text().unwrap();
+        let markdown = trace.markdown().unwrap();
+        assert_eq!(trace.rounds.len(), 3);
+        assert_eq!(trace.classes().len(), 4);
+        assert_eq!(
+            markdown
+                .lines()
+                .filter(|line| line.contains("Splitter"))
+                .count(),
+            text.lines()
+                .filter(|line| line.starts_with("round"))
+                .count()
+        );
+        assert_eq!(
+            markdown.matches("splits into").count(),
+            text.matches("splits into").count()
+        );
+        assert!(markdown.ends_with("4 states in the minimal DFA.\n"));
+        assert!(text.ends_with("4 states in the minimal DFA\n"));
+    }
+}
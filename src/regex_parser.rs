@@ -1,131 +1,335 @@
-use crate::symbol::ASCII;
-
-use phf::phf_map;
-
-pub static OPERATORS: phf::Map<char, usize> = phf_map! {
-    '*' => 10,
-    '+' => 10,
-    '?' => 10,
-    '.' => 5,
-    '|' => 0,
-};
-
-// Shunting-yard, and concatenation in regex.
-
-// We want to export this as an object.  Or maybe not.  Yeah, no need.
-
-// Also need to write a regex verifier.
-// Verify that the string is all ASCII, then verify that the string is a valid regex (might be done in the parsing stage).
-
-// First, a function that converts a simple regex to a simple regex with concatenation operator '.'
-pub fn add_concatenation(regex: &String) -> String {
-    let mut output = String::new();
-    let len = regex.len();
-    let regex = regex
-        .as_bytes()
-        .iter()
-        .map(|&c| c as char)
-        .collect::<Vec<char>>();
-
-    for i in 0..len {
-        let c = regex[i];
-        output.push(c);
-
-        // TODO: Later, add a `if c == '\' to factor in escaped characters.
-        if c == '(' || c == '|' {
-            continue;
-        } else if c == ')' || ASCII.contains(&c) {
-            if i + 1 < len {
-                // Look ahead a char; if it is a left parentheses or another character add a concatenation.
-                match regex[i + 1] {
-                    next if next == ')' || OPERATORS.contains_key(&next) => continue,
-                    '(' => output.push('.'),
-                    next if ASCII.contains(&next) => output.push('.'),
-                    // ! ASCII also contains ')', '|', and such... make note of this in case errors arise.
-                    _ => continue,
-                }
-            }
-        }
-    }
-    output
-}
-
-pub fn to_postfix(regex: &String) -> String {
-    let mut output = String::new();
-    let mut opstack: Vec<char> = Vec::new();
-
-    for c in regex.chars() {
-        // println!("{:?}, CH - '{}'", opstack, c);
-        match c {
-            '(' => opstack.push(c),
-            ')' => {
-                while let Some(op) = opstack.pop() {
-                    // println!("paren: {:?}", opstack);
-                    if op == '(' {
-                        break;
-                    }
-                    output.push(op);
-                }
-            }
-            op if OPERATORS.contains_key(&c) => {
-                while let Some(&top) = opstack.last() {
-                    if top == '(' {
-                        opstack.push(op);
-                        break;
-                    } else {
-                        match (OPERATORS.get(&top), OPERATORS.get(&op)) {
-                            (Some(&topprec), Some(&opprec)) => {
-                                if topprec >= opprec {
-                                    opstack.pop();
-                                    output.push(top);
-                                } else {
-                                    opstack.push(op);
-                                    break;
-                                }
-                            }
-                            (_, _) => panic!(),
-                        }
-                    }
-                }
-                if opstack.is_empty() {
-                    opstack.push(op);
-                }
-            }
-            c if ASCII.contains(&c) => {
-                output.push(c);
-            }
-            c => {
-                // output.push(c);
-                // println!("char: {} :", c);
-                println!("invalid: {}", c);
-            }
-        }
-    }
-
-    // Push remaining operations to output, starting from end.
-    for &op in opstack.iter().rev() {
-        output.push(op);
-    }
-    println!("{}", output);
-    output
-}
-
-use crate::fa::FA;
-use crate::thompsons;
-
-// Now, turn the postfix notation into something wrapped with functions OR, AND, and STAR (repeat).
-pub fn parse_to_nfa(input: &String) -> Option<FA> {
-    let with_concat = add_concatenation(input);
-    let postfix = to_postfix(&with_concat);
-    thompsons::parse_to_finite_automata(&postfix)
-
-}
-
-pub fn parse_to_dfa(input: &String) -> Option<FA> {
-    if let Some(nfa) = parse_to_nfa(input) {
-        println!("{}", nfa);
-        Some(nfa.dfa_from())
-    } else {
-        None
-    }
-}
\ No newline at end of file
+use crate::symbol::{ASCII, EPSILON};
+
+use phf::phf_map;
+
+pub static OPERATORS: phf::Map<char, usize> = phf_map! {
+    '*' => 10,
+    '+' => 10,
+    '?' => 10,
+    // Complement is the one prefix operator: `~a*` is the complement of `a*`.
+    '~' => 7,
+    '.' => 5,
+    '&' => 2,
+    '|' => 0,
+};
+
+// Shunting-yard, and concatenation in regex.
+
+// We want to export this as an object.  Or maybe not.  Yeah, no need.
+
+// Also need to write a regex verifier.
+// Verify that the string is all ASCII, then verify that the string is a valid regex (might be done in the parsing stage).
+
+// First, a function that converts a simple regex to a simple regex with concatenation operator '.'
+pub fn add_concatenation(regex: &str) -> String {
+    let mut output = String::new();
+    let regex = regex.chars().collect::<Vec<char>>();
+    let len = regex.len();
+    let mut in_class = false;
+    let mut i = 0;
+
+    while i < len {
+        let c = regex[i];
+        output.push(c);
+        i += 1;
+
+        // A class is copied as it is up to its closing bracket, which then ends an operand.
+        if in_class {
+            if c != ']' {
+                continue;
+            }
+            in_class = false;
+        } else if c == '[' {
+            in_class = true;
+            continue;
+        }
+
+        // An escaped character is copied with its backslash, and is an operand whatever it is.
+        let escaped = c == '\\' && i < len;
+        if escaped {
+            output.push(regex[i]);
+            i += 1;
+        }
+
+        if !escaped && (c == '(' || c == '|' || c == '&' || c == '~') {
+            continue;
+        } else if (escaped || c == ')' || ASCII.contains(&c) || c == EPSILON) && i < len {
+            // Look ahead a char; if it is a left parentheses or another character add a concatenation.
+            match regex[i] {
+                // A complement starts a new operand, like a left parenthesis does.
+                '~' => output.push('.'),
+                next if next == ')' || OPERATORS.contains_key(&next) => continue,
+                '(' => output.push('.'),
+                next if ASCII.contains(&next) || next == EPSILON => output.push('.'),
+                // ! ASCII also contains ')', '|', and such... make note of this in case errors arise.
+                _ => continue,
+            }
+        }
+    }
+    output
+}
+
+pub fn to_postfix(regex: &str) -> String {
+    let mut output = String::new();
+    let mut opstack: Vec<char> = Vec::new();
+    let mut in_class = false;
+    let mut chars = regex.chars();
+    let mut after_open = false;
+
+    while let Some(c) = chars.next() {
+        // println!("{:?}, CH - '{}'", opstack, c);
+        // Operator characters inside a class are members of it, so the whole class goes to the output untouched.
+        if in_class {
+            output.push(c);
+            in_class = c != ']';
+            continue;
+        }
+        // An empty group, `()`, matches the empty string.
+        if c == ')' && after_open {
+            output.push(EPSILON);
+        }
+        after_open = c == '(';
+        match c {
+            '[' => {
+                output.push(c);
+                in_class = true;
+            }
+            // An escaped character goes to the output with its backslash, so it is not taken for an operator later.
+            '\\' => {
+                output.push(c);
+                output.extend(chars.next());
+            }
+            '(' => opstack.push(c),
+            ')' => {
+                while let Some(op) = opstack.pop() {
+                    // println!("paren: {:?}", opstack);
+                    if op == '(' {
+                        // Kept as a postfix operator that marks its operand as a group.
+                        output.push(')');
+                        break;
+                    }
+                    output.push(op);
+                }
+            }
+            // A prefix operator has no left operand, so nothing on the stack can be finished yet.
+            '~' => opstack.push(c),
+            op if OPERATORS.contains_key(&c) => {
+                while let Some(&top) = opstack.last() {
+                    if top == '(' {
+                        opstack.push(op);
+                        break;
+                    } else {
+                        match (OPERATORS.get(&top), OPERATORS.get(&op)) {
+                            (Some(&topprec), Some(&opprec)) => {
+                                if topprec >= opprec {
+                                    opstack.pop();
+                                    output.push(top);
+                                } else {
+                                    opstack.push(op);
+                                    break;
+                                }
+                            }
+                            (_, _) => panic!(),
+                        }
+                    }
+                }
+                if opstack.is_empty() {
+                    opstack.push(op);
+                }
+            }
+            c if ASCII.contains(&c) || c == EPSILON => {
+                output.push(c);
+            }
+            // Characters outside the alphabet are left in, for building the expression to reject.
+            c => output.push(c),
+        }
+    }
+
+    // Push remaining operations to output, starting from end.
+    for &op in opstack.iter().rev() {
+        output.push(op);
+    }
+    output
+}
+
+use crate::derivatives;
+use crate::fa::FA;
+use crate::followpos::Positions;
+use crate::glushkov;
+use crate::thompsons::{self, Expr};
+
+// The ways a regex can be turned into an automaton.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Construction {
+    // Thompson's construction, giving an NFA with empty transitions.
+    Thompson,
+    // Brzozowski derivatives, giving a DFA directly.
+    Derivatives,
+    // The Dragon Book's construction from followpos, also giving a DFA directly.
+    Followpos,
+    // The Glushkov construction, giving an NFA without empty transitions.
+    Glushkov,
+}
+
+impl Construction {
+    pub const NAMES: &'static [&'static str] = &["thompson", "derivatives", "followpos", "glushkov"];
+}
+
+impl std::str::FromStr for Construction {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "thompson" => Ok(Construction::Thompson),
+            "derivatives" => Ok(Construction::Derivatives),
+            "followpos" => Ok(Construction::Followpos),
+            "glushkov" => Ok(Construction::Glushkov),
+            _ => Err(format!("unknown construction: {}", name)),
+        }
+    }
+}
+
+// Now, turn the postfix notation into something wrapped with functions OR, AND, and STAR (repeat).
+pub fn parse_to_nfa(input: &str) -> Option<FA> {
+    parse_to_expr(input).map(thompsons::expr_to_finite_automata)
+}
+
+// Takes the names out of named groups, `(?<name>...)`, leaving plain parentheses.  The names are returned in the order
+// of the groups' opening parentheses, with `None` for unnamed groups.
+fn take_group_names(regex: &str) -> Option<(String, Vec<Option<String>>)> {
+    let mut output = String::new();
+    let mut names = Vec::new();
+    let mut chars = regex.chars().peekable();
+    let mut in_class = false;
+
+    while let Some(c) = chars.next() {
+        output.push(c);
+        if in_class {
+            in_class = c != ']';
+        } else if c == '\\' {
+            // An escaped parenthesis does not open a group.
+            output.extend(chars.next());
+        } else if c == '[' {
+            in_class = true;
+        } else if c == '(' {
+            if chars.peek() != Some(&'?') {
+                names.push(None);
+                continue;
+            }
+            chars.next();
+            if chars.next()? != '<' {
+                return None;
+            }
+            let mut name = String::new();
+            loop {
+                match chars.next()? {
+                    '>' => break,
+                    c if c.is_ascii_alphanumeric() || c == '_' => name.push(c),
+                    _ => return None,
+                }
+            }
+            if name.is_empty() {
+                return None;
+            }
+            names.push(Some(name));
+        }
+    }
+    Some((output, names))
+}
+
+// Gives the groups their names, visiting them in the order of their opening parentheses.
+fn name_groups(expr: &mut Expr, names: &mut impl Iterator<Item = Option<String>>) {
+    match expr {
+        Expr::Empty | Expr::Just(_) | Expr::Class(_) => {}
+        Expr::Or(e1, e2) | Expr::And(e1, e2) | Expr::Intersect(e1, e2) => {
+            name_groups(e1, names);
+            name_groups(e2, names);
+        }
+        Expr::Star(e) | Expr::Plus(e) | Expr::QMark(e) | Expr::Complement(e) => name_groups(e, names),
+        Expr::Group(name, e) => {
+            *name = names.next().flatten();
+            name_groups(e, names);
+        }
+    }
+}
+
+// Parses a regex into its syntax tree, without building an automaton.
+pub fn parse_to_expr(input: &str) -> Option<Expr> {
+    let (input, names) = take_group_names(input)?;
+    let with_concat = add_concatenation(&input);
+    let postfix = to_postfix(&with_concat);
+    let mut expr = thompsons::parse_string_to_expr(&postfix)?;
+    name_groups(&mut expr, &mut names.into_iter());
+    Some(expr)
+}
+
+pub fn parse_with(input: &str, construction: Construction) -> Result<FA, String> {
+    let expr = parse_to_expr(input).ok_or_else(|| format!("failed to parse: {}", input))?;
+    match construction {
+        Construction::Thompson => Ok(thompsons::expr_to_finite_automata(expr)),
+        Construction::Derivatives => Ok(derivatives::dfa_from_expr(&expr)),
+        Construction::Followpos => Ok(Positions::new(&expr)?.dfa()),
+        Construction::Glushkov => Ok(glushkov::nfa(&Positions::new(&expr)?)),
+    }
+}
+
+pub fn parse_to_dfa(input: &str) -> Option<FA> {
+    parse_to_nfa(input).map(|nfa| nfa.dfa_from())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbol::Symbol;
+
+    fn accepts(regex: &str, input: &str) -> bool {
+        parse_to_dfa(regex).unwrap().dfa_accepts(input.to_string())
+    }
+
+    #[test]
+    fn escaped_operators_are_literals() {
+        assert!(accepts("a\\&b", "a&b"));
+        assert!(accepts("\\~a", "~a"));
+        assert!(!accepts("\\~a", "b"));
+        assert!(accepts("a\\*", "a*"));
+        assert!(!accepts("a\\*", "aa"));
+        assert!(accepts("(x\\|y)+", "x|yx|y"));
+        assert!(accepts("\\\\", "\\"));
+        assert!(accepts("\\(a\\)", "(a)"));
+    }
+
+    #[test]
+    fn escapes_concatenate_like_characters() {
+        assert_eq!(add_concatenation("a\\&b"), "a.\\&.b");
+        assert_eq!(add_concatenation("\\&*"), "\\&*");
+        assert_eq!(to_postfix(&add_concatenation("a\\|b")), "a\\|.b.");
+    }
+
+    #[test]
+    fn escaped_parentheses_are_not_groups() {
+        let expr = parse_to_expr("\\((?<x>a)").unwrap();
+        let group = Expr::Group(Some("x".to_string()), Box::new(Expr::Just(Symbol::Char('a'))));
+        let open = Expr::Just(Symbol::Char('('));
+        assert_eq!(expr, Expr::And(Box::new(open), Box::new(group)));
+    }
+
+    #[test]
+    fn trailing_backslash_fails() {
+        assert!(parse_to_expr("a\\").is_none());
+    }
+
+    #[test]
+    fn malformed_regexes_fail() {
+        for regex in ["aéb", "*a", "a|", "~", "(a", "+"] {
+            assert!(parse_to_expr(regex).is_none(), "{}", regex);
+        }
+    }
+
+    #[test]
+    fn empty_groups_match_the_empty_string() {
+        assert_eq!(parse_to_expr("()"), parse_to_expr("(ε)"));
+        assert!(accepts("()", ""));
+        assert!(accepts("a()b", "ab"));
+        assert!(!accepts("a()b", "a"));
+        assert!(accepts("(()|a)*b", "aab"));
+        assert!(accepts("\\\\()", "\\"));
+    }
+}
@@ -0,0 +1,148 @@
+// Matching against many patterns at once.  The patterns' automata are joined under one starting state and
+// determinized together, so every state of the DFA is a set of states drawn from all of the patterns.  Rather than
+// collapsing each set to accept or reject, the DFA keeps the ids of the patterns whose acceptors the set contains, and
+// one pass over the input finds every pattern that matches the whole of it.
+
+use crate::fa::{State, FA};
+use crate::symbol::Symbol;
+use crate::transition::Transition;
+
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Clone)]
+pub struct RegexSet {
+    dfa: FA,
+    // The patterns each DFA state accepts for.  States that accept for none are left out.
+    labels: HashMap<State, BTreeSet<usize>>,
+}
+
+impl RegexSet {
+    // Pattern ids are the patterns' indices in `patterns`.
+    pub fn new(patterns: &[FA]) -> Self {
+        let mut nfa = FA::new();
+        let mut pattern_of = HashMap::new();
+        let mut starts = Vec::new();
+        // Each pattern's states are moved above all those before it, and the new start goes above them all.
+        for (id, pattern) in patterns.iter().enumerate() {
+            let offset = nfa.fresh_state();
+            starts.push(pattern.starting() + offset);
+            for &s in pattern.states() {
+                nfa.add_state(s + offset);
+            }
+            for t in pattern.delta() {
+                nfa.add_transition(Transition::from(
+                    t.sym(),
+                    t.start() + offset,
+                    t.end() + offset,
+                ));
+            }
+            for &a in pattern.accepting() {
+                nfa.add_acceptor(a + offset);
+                pattern_of.insert(a + offset, id);
+            }
+        }
+        let start = nfa.fresh_state();
+        nfa.add_state(start);
+        for s in starts {
+            nfa.add_transition(Transition::from(Symbol::Empty, start, s));
+        }
+        nfa.set_start(start);
+
+        let dfa = nfa.dfa_from();
+        let labels = dfa
+            .states()
+            .iter()
+            .map(|&state| {
+                let subset = dfa.subset_of(state).into_iter().flatten();
+                let ids = subset.filter_map(|s| pattern_of.get(s).copied());
+                (state, ids.collect::<BTreeSet<usize>>())
+            })
+            .filter(|(_, ids)| !ids.is_empty())
+            .collect();
+
+        Self { dfa, labels }
+    }
+    pub fn dfa(&self) -> &FA {
+        &self.dfa
+    }
+    // The ids of the patterns a DFA state accepts for.
+    pub fn patterns_of(&self, state: State) -> BTreeSet<usize> {
+        self.labels.get(&state).cloned().unwrap_or_default()
+    }
+    // The ids of the patterns that match the whole input.
+    pub fn matches(&self, input: &str) -> BTreeSet<usize> {
+        let mut state = self.dfa.starting();
+        for c in input.chars() {
+            match self.dfa.next_state(state, c) {
+                Some(next) => state = next,
+                None => return BTreeSet::new(),
+            }
+        }
+        self.patterns_of(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fa_reader;
+    use crate::regex_parser::parse_to_nfa;
+
+    fn set(patterns: &[&str]) -> RegexSet {
+        let fas: Vec<FA> = patterns.iter().map(|p| parse_to_nfa(p).unwrap()).collect();
+        RegexSet::new(&fas)
+    }
+
+    fn ids(ids: &[usize]) -> BTreeSet<usize> {
+        ids.iter().copied().collect()
+    }
+
+    #[test]
+    fn overlapping_patterns_all_match() {
+        let set = set(&["if|else", "[a-z]+", "[0-9]+"]);
+        assert_eq!(set.matches("if"), ids(&[0, 1]));
+        assert_eq!(set.matches("else"), ids(&[0, 1]));
+        assert_eq!(set.matches("iff"), ids(&[1]));
+        assert_eq!(set.matches("42"), ids(&[2]));
+    }
+
+    #[test]
+    fn pattern_matching_the_empty_string() {
+        let set = set(&["a*", "b", "ε"]);
+        assert_eq!(set.matches(""), ids(&[0, 2]));
+        assert_eq!(set.matches("aa"), ids(&[0]));
+        assert_eq!(set.matches("b"), ids(&[1]));
+    }
+
+    #[test]
+    fn input_no_pattern_matches() {
+        let set = set(&["if|else", "[0-9]+"]);
+        assert!(set.matches("if1").is_empty());
+        assert!(set.matches("").is_empty());
+        assert!(set.matches("?").is_empty());
+    }
+
+    #[test]
+    fn patterns_with_sparse_state_ids() {
+        // a(b|c), with states numbered from 5 and gaps between them.
+        let spec = "5 :: 1\na -> 9\n9 :: 2\nb -> 14\nc -> 14\n14 => 0\n";
+        let path = std::env::temp_dir().join(format!("regex_set_{}.fa", std::process::id()));
+        std::fs::write(&path, spec).unwrap();
+        let mut read = fa_reader::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // The reader leaves the start at 0, so it is set to the first state here.
+        read.set_start(5);
+
+        let set = RegexSet::new(&[
+            parse_to_nfa("ab*").unwrap(),
+            read,
+            parse_to_nfa("a[bx]").unwrap(),
+        ]);
+        assert_eq!(set.matches("ab"), ids(&[0, 1, 2]));
+        assert_eq!(set.matches("ac"), ids(&[1]));
+        assert_eq!(set.matches("ax"), ids(&[2]));
+        assert_eq!(set.matches("a"), ids(&[0]));
+        assert_eq!(set.matches("abb"), ids(&[0]));
+        assert!(set.matches("b").is_empty());
+    }
+}
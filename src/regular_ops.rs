@@ -0,0 +1,145 @@
+// The regular operations on finite automata: concatenation, union and the repetitions, joined with empty transitions
+// the way Thompson's construction joins its pieces.  The states of the left operand keep their ids, and every other
+// state gets an id above all of them, so automata from anywhere (a `.fa` file, a regex) can be combined safely.
+
+use crate::fa::{State, FA};
+use crate::symbol::Symbol;
+use crate::transition::Transition;
+
+impl FA {
+    // Copies the states and transitions of `other` into this automaton, with `offset` added to every id.  Its
+    // starting and accepting states are left to the caller.
+    fn absorb(&mut self, other: &FA, offset: State) {
+        for &s in other.states() {
+            self.add_state(s + offset);
+        }
+        for t in other.delta() {
+            self.add_transition(Transition::from(
+                t.sym(),
+                t.start() + offset,
+                t.end() + offset,
+            ));
+        }
+    }
+    // Accepts a string of this automaton followed by a string of `other`.
+    pub fn concat(&self, other: &FA) -> FA {
+        let mut fa = self.clone();
+        let offset = self.fresh_state();
+        fa.absorb(other, offset);
+
+        fa.accepting_mut().clear();
+        for &a in self.accepting() {
+            fa.add_transition(Transition::from(
+                Symbol::Empty,
+                a,
+                other.starting() + offset,
+            ));
+        }
+        for &a in other.accepting() {
+            fa.add_acceptor(a + offset);
+        }
+        fa
+    }
+    // Accepts the strings of either automaton, from a new starting state with empty transitions to both of theirs.  The
+    // result is an NFA; `union_product` gives a DFA for the same strings.
+    pub fn union(&self, other: &FA) -> FA {
+        let mut fa = self.clone();
+        let offset = self.fresh_state();
+        fa.absorb(other, offset);
+        for &a in other.accepting() {
+            fa.add_acceptor(a + offset);
+        }
+
+        let start = fa.fresh_state();
+        fa.add_state(start);
+        fa.add_transition(Transition::from(Symbol::Empty, start, self.starting()));
+        fa.add_transition(Transition::from(
+            Symbol::Empty,
+            start,
+            other.starting() + offset,
+        ));
+        fa.set_start(start);
+        fa
+    }
+    // Accepts any number of strings of this automaton in a row.  A new, accepting starting state leads into the old
+    // one, and every acceptor leads back to it.
+    pub fn star(&self) -> FA {
+        let mut fa = self.clone();
+        let start = fa.fresh_state();
+        fa.add_state(start);
+        fa.add_transition(Transition::from(Symbol::Empty, start, self.starting()));
+        for &a in self.accepting() {
+            fa.add_transition(Transition::from(Symbol::Empty, a, start));
+        }
+        fa.accepting_mut().clear();
+        fa.add_acceptor(start);
+        fa.set_start(start);
+        fa
+    }
+    // Accepts one or more strings of this automaton in a row, by leading every acceptor back to the start.
+    pub fn plus(&self) -> FA {
+        let mut fa = self.clone();
+        for &a in self.accepting() {
+            fa.add_transition(Transition::from(Symbol::Empty, a, self.starting()));
+        }
+        fa
+    }
+    // Accepts the strings of this automaton and the empty string, from a new, accepting starting state.
+    pub fn optional(&self) -> FA {
+        let mut fa = self.clone();
+        let start = fa.fresh_state();
+        fa.add_state(start);
+        fa.add_transition(Transition::from(Symbol::Empty, start, self.starting()));
+        fa.add_acceptor(start);
+        fa.set_start(start);
+        fa
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivalence::{equivalence, Equivalence};
+    use crate::regex_parser::parse_to_nfa;
+
+    fn same(fa: &FA, regex: &str) {
+        let expected = parse_to_nfa(regex).unwrap();
+        assert_eq!(
+            equivalence(fa, &expected),
+            Equivalence::Equivalent,
+            "{}",
+            regex
+        );
+    }
+
+    fn nfa(regex: &str) -> FA {
+        parse_to_nfa(regex).unwrap()
+    }
+
+    // An automaton combined with itself shares every id with the other operand.
+    #[test]
+    fn concat() {
+        same(&nfa("a|b").concat(&nfa("c*")), "(a|b)c*");
+        let ab = nfa("ab");
+        same(&ab.concat(&ab), "abab");
+        same(&nfa("a*").concat(&nfa("a&b")), "a&b");
+    }
+
+    #[test]
+    fn union() {
+        same(&nfa("ab").union(&nfa("c+")), "ab|c+");
+        let a = nfa("a*b");
+        same(&a.union(&a), "a*b");
+        same(&nfa("x").union(&nfa("a&b")), "x");
+    }
+
+    #[test]
+    fn repetitions() {
+        same(&nfa("ab").star(), "(ab)*");
+        same(&nfa("ab").plus(), "(ab)+");
+        same(&nfa("ab").optional(), "ε|ab");
+        // Repeating an automaton whose start is accepting and looped back to.
+        same(&nfa("a*b*").star(), "(a|b)*");
+        same(&nfa("a&b").star(), "ε");
+    }
+}
@@ -0,0 +1,175 @@
+// Draws accepted strings of a given length uniformly at random, and rejected strings one edit away from accepted ones.
+
+use crate::analysis::{Count, Language};
+use crate::fa::FA;
+
+// A seedable pseudorandom generator (splitmix64), so a fuzz input can be reproduced from its seed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    // A number in `0..n`, each equally likely.  `n` must not be zero.
+    pub fn below_usize(&mut self, n: usize) -> usize {
+        self.below(&Count::from(n as u64))
+            .limbs()
+            .first()
+            .map_or(0, |&l| l as usize)
+    }
+    // A number in `0..n`, each equally likely; `n` must not be zero.  Draws numbers with as many bits as `n` until one is less than it.
+    pub fn below(&mut self, n: &Count) -> Count {
+        let bits = n.bits();
+        loop {
+            let mut limbs = (0..bits.div_ceil(64))
+                .map(|_| self.next_u64())
+                .collect::<Vec<u64>>();
+            // Bits above the top of `n` are cleared, unless it fills its top limb.
+            let top_bits = bits % 64;
+            if top_bits > 0 {
+                if let Some(top) = limbs.last_mut() {
+                    *top &= (1u64 << top_bits) - 1;
+                }
+            }
+            let r = Count::from_limbs(limbs);
+            if r < *n {
+                return r;
+            }
+        }
+    }
+}
+
+pub struct Sampler {
+    // The minimal DFA has no dead states, so every state counted is one a string can pass through.
+    language: Language,
+    alphabet: Vec<char>,
+}
+
+impl Sampler {
+    pub fn new(fa: &FA) -> Self {
+        let language = Language::new(fa);
+        let alphabet = language.dfa().alphabet();
+        Self { language, alphabet }
+    }
+    // How many accepted strings have exactly `length` characters.
+    pub fn count(&mut self, length: usize) -> Count {
+        self.language.count_of_length(length)
+    }
+    // An accepted string of exactly `length` characters, every one equally likely, or `None` if there are none.
+    // Each character is drawn with probability proportional to the number of ways the rest of the string can be
+    // completed after it.
+    pub fn accepted(&mut self, length: usize, rng: &mut Rng) -> Option<String> {
+        let mut state = self.language.dfa().starting();
+        let total = self.count(length);
+        if total.is_zero() {
+            return None;
+        }
+
+        let mut string = String::new();
+        for remaining in (0..length).rev() {
+            let mut pick = rng.below(&self.language.paths(remaining + 1, state));
+            for i in 0..self.alphabet.len() {
+                let c = self.alphabet[i];
+                let next = match self.language.dfa().next_state(state, c) {
+                    Some(next) => next,
+                    None => continue,
+                };
+                let ways = self.language.paths(remaining, next);
+                if pick < ways {
+                    string.push(c);
+                    state = next;
+                    break;
+                }
+                pick = pick.sub(&ways);
+            }
+        }
+        Some(string)
+    }
+    // A rejected string one edit (a substitution, insertion or deletion over the automaton's alphabet) away from an
+    // accepted string of `length` characters, or `None` if none turned up in a fair number of tries.
+    pub fn rejected_near(&mut self, length: usize, rng: &mut Rng) -> Option<String> {
+        const TRIES: usize = 100;
+        if self.alphabet.is_empty() {
+            return None;
+        }
+
+        for _ in 0..TRIES {
+            let mut chars = self.accepted(length, rng)?.chars().collect::<Vec<char>>();
+            let c = self.alphabet[rng.below_usize(self.alphabet.len())];
+            match rng.below_usize(3) {
+                0 if !chars.is_empty() => {
+                    let i = rng.below_usize(chars.len());
+                    chars[i] = c;
+                }
+                1 if !chars.is_empty() => {
+                    chars.remove(rng.below_usize(chars.len()));
+                }
+                _ => chars.insert(rng.below_usize(chars.len() + 1), c),
+            }
+            let string = chars.into_iter().collect::<String>();
+            if !self.language.dfa().dfa_accepts(string.clone()) {
+                return Some(string);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex_parser::parse_to_nfa;
+
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn below_stays_below() {
+        let mut rng = Rng::new(7);
+        // Two limbs, with only one bit used in the top one.
+        let n = Count::from_limbs(vec![3, 1]);
+        for _ in 0..1000 {
+            assert!(rng.below(&n) < n);
+        }
+        assert!(rng.below(&Count::from(1)).is_zero());
+        for _ in 0..1000 {
+            assert!(rng.below_usize(3) < 3);
+        }
+    }
+
+    #[test]
+    fn accepted_strings_cover_the_language() {
+        let fa = parse_to_nfa("(a|b)(a|bc|c)").unwrap();
+        let mut sampler = Sampler::new(&fa);
+        let mut rng = Rng::new(1);
+        assert_eq!(sampler.count(2), Count::from(4));
+        let mut seen = BTreeSet::new();
+        for _ in 0..200 {
+            let string = sampler.accepted(2, &mut rng).unwrap();
+            assert!(fa.dfa_from().dfa_accepts(string.clone()));
+            seen.insert(string);
+        }
+        assert_eq!(seen.len(), 4);
+        assert_eq!(sampler.accepted(4, &mut rng), None);
+    }
+
+    #[test]
+    fn rejected_strings_are_rejected() {
+        let fa = parse_to_nfa("(ab)*").unwrap();
+        let mut sampler = Sampler::new(&fa);
+        let mut rng = Rng::new(3);
+        for _ in 0..50 {
+            let string = sampler.rejected_near(4, &mut rng).unwrap();
+            assert!(!fa.dfa_from().dfa_accepts(string));
+        }
+    }
+}
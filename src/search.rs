@@ -0,0 +1,294 @@
+// Finds matches of an automaton inside a longer string, rather than testing the whole string.
+//
+// Match starts are found first, in one backward pass over the haystack with a DFA for `.*` followed by the reversed
+// language: after reading the haystack backwards from its end down to position i, that DFA accepts exactly when a
+// match starts at i.  The leftmost start is then run forwards to find where the match ends.
+//
+// Leftmost-longest matching ends the match as late as possible, by running the forward DFA until it dies.
+// Leftmost-first matching ends it where a backtracking matcher would: the NFA's states are simulated as a list of
+// threads in priority order, and each state's transitions are tried in the order they were added, so with
+// Thompson's construction the left side of `|` comes first and the repetitions are greedy.
+
+use crate::fa::{State, FA};
+use crate::symbol::Symbol;
+use crate::transition::Transition;
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MatchKind {
+    LeftmostFirst,
+    LeftmostLongest,
+}
+
+#[derive(Debug, Clone)]
+pub struct Searcher {
+    kind: MatchKind,
+    nfa: FA,
+    forward: FA,
+    reverse: FA,
+}
+
+impl Searcher {
+    pub fn new(fa: &FA, kind: MatchKind) -> Self {
+        // The reversed language behind a state that loops on every character.  A character the automaton never
+        // reads leaves only that state alive, so the reverse DFA goes back to its start wherever it has no
+        // transition.
+        let mut reverse = fa.reverse();
+        let any = reverse.fresh_state();
+        reverse.add_state(any);
+        for c in fa.alphabet() {
+            reverse.add_transition(Transition::from(Symbol::Char(c), any, any));
+        }
+        reverse.add_transition(Transition::from(Symbol::Empty, any, reverse.starting()));
+        reverse.set_start(any);
+
+        Self {
+            kind,
+            nfa: fa.clone(),
+            forward: fa.to_dfa(),
+            reverse: reverse.dfa_from(),
+        }
+    }
+    // The leftmost match in the haystack, as a range of byte offsets.
+    pub fn find(&self, haystack: &str) -> Option<Range<usize>> {
+        self.find_iter(haystack).next()
+    }
+    // Every match in the haystack, left to right, with none overlapping.
+    pub fn find_iter<'s, 'h>(&'s self, haystack: &'h str) -> FindIter<'s, 'h> {
+        FindIter {
+            searcher: self,
+            haystack,
+            starts: self.starts(haystack),
+            at: Some(0),
+        }
+    }
+    // Whether a match starts at each byte offset, from 0 to the haystack's length.
+    fn starts(&self, haystack: &str) -> Vec<bool> {
+        let mut starts = vec![false; haystack.len() + 1];
+        let mut state = self.reverse.starting();
+        starts[haystack.len()] = self.reverse.is_accepting(state);
+        for (i, c) in haystack.char_indices().rev() {
+            state = self
+                .reverse
+                .next_state(state, c)
+                .unwrap_or(self.reverse.starting());
+            starts[i] = self.reverse.is_accepting(state);
+        }
+        starts
+    }
+    // Where the match starting at `start` ends, if one does.
+    fn end_from(&self, haystack: &str, start: usize) -> Option<usize> {
+        match self.kind {
+            MatchKind::LeftmostLongest => self.longest_end(haystack, start),
+            MatchKind::LeftmostFirst => self.first_end(haystack, start),
+        }
+    }
+    fn longest_end(&self, haystack: &str, start: usize) -> Option<usize> {
+        let mut state = self.forward.starting();
+        let mut end = None;
+        if self.forward.is_accepting(state) {
+            end = Some(start);
+        }
+        for (i, c) in haystack[start..].char_indices() {
+            match self.forward.next_state(state, c) {
+                Some(next) => state = next,
+                None => break,
+            }
+            if self.forward.is_accepting(state) {
+                end = Some(start + i + c.len_utf8());
+            }
+        }
+        end
+    }
+    fn first_end(&self, haystack: &str, start: usize) -> Option<usize> {
+        let mut threads = Vec::new();
+        add_thread(
+            &self.nfa,
+            &mut threads,
+            &mut HashSet::new(),
+            self.nfa.starting(),
+        );
+        let mut end = None;
+        let mut at = start;
+        let mut chars = haystack[start..].chars();
+
+        loop {
+            // A thread that accepts ends the match for now, and the threads after it, which it outranks, are
+            // dropped.  The threads before it may still find a better match later on, and so may the accepting
+            // thread itself when its state has transitions out, as with constructions other than Thompson's.
+            if let Some(k) = threads.iter().position(|&s| self.nfa.is_accepting(s)) {
+                end = Some(at);
+                threads.truncate(k + 1);
+            }
+            let c = match chars.next() {
+                Some(c) if !threads.is_empty() => c,
+                _ => break,
+            };
+            let mut next = Vec::new();
+            let mut seen = HashSet::new();
+            for &s in &threads {
+                for t in self.nfa.transitions_of(s).into_iter().flatten() {
+                    if t.sym() == Symbol::Char(c) {
+                        add_thread(&self.nfa, &mut next, &mut seen, t.end());
+                    }
+                }
+            }
+            threads = next;
+            at += c.len_utf8();
+        }
+        end
+    }
+}
+
+// Adds a state and everything its empty transitions lead to, depth first in the order of the transitions.  A state
+// already on the list keeps its earlier, higher priority place.
+fn add_thread(nfa: &FA, threads: &mut Vec<State>, seen: &mut HashSet<State>, state: State) {
+    if !seen.insert(state) {
+        return;
+    }
+    threads.push(state);
+    for t in nfa.transitions_of(state).into_iter().flatten() {
+        if t.sym() == Symbol::Empty {
+            add_thread(nfa, threads, seen, t.end());
+        }
+    }
+}
+
+pub struct FindIter<'s, 'h> {
+    searcher: &'s Searcher,
+    haystack: &'h str,
+    starts: Vec<bool>,
+    // Where the search for the next match begins, or `None` once the haystack is used up.
+    at: Option<usize>,
+}
+
+impl Iterator for FindIter<'_, '_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let from = self.at?;
+        let start = (from..=self.haystack.len()).find(|&i| self.starts[i])?;
+        let end = self.searcher.end_from(self.haystack, start)?;
+
+        // After an empty match, the next search starts a character later, or it would find the same match again.
+        self.at = if end > start {
+            Some(end)
+        } else {
+            self.haystack[end..]
+                .chars()
+                .next()
+                .map(|c| end + c.len_utf8())
+        };
+        Some(start..end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex_parser::{parse_to_nfa, parse_with, Construction};
+
+    fn searcher(regex: &str, kind: MatchKind) -> Searcher {
+        Searcher::new(&parse_to_nfa(regex).unwrap(), kind)
+    }
+
+    fn all(regex: &str, kind: MatchKind, haystack: &str) -> Vec<Range<usize>> {
+        searcher(regex, kind).find_iter(haystack).collect()
+    }
+
+    #[test]
+    fn empty_match_at_the_start() {
+        assert_eq!(
+            searcher("a*", MatchKind::LeftmostFirst).find("baaa"),
+            Some(0..0)
+        );
+        assert_eq!(
+            searcher("a*", MatchKind::LeftmostLongest).find(""),
+            Some(0..0)
+        );
+    }
+
+    #[test]
+    fn star_between_empty_matches() {
+        // After an empty match the search moves on a character; after a non-empty one, an empty match at its end
+        // still counts.
+        for kind in [MatchKind::LeftmostFirst, MatchKind::LeftmostLongest] {
+            assert_eq!(all("a*", kind, "baaa"), vec![0..0, 1..4, 4..4]);
+            assert_eq!(all("b?", kind, "abab"), vec![0..0, 1..2, 2..2, 3..4, 4..4]);
+        }
+    }
+
+    #[test]
+    fn first_and_longest_differ_on_alternations() {
+        assert_eq!(
+            searcher("sam|samwise", MatchKind::LeftmostFirst).find("samwise"),
+            Some(0..3)
+        );
+        assert_eq!(
+            searcher("sam|samwise", MatchKind::LeftmostLongest).find("samwise"),
+            Some(0..7)
+        );
+        assert_eq!(
+            searcher("samwise|sam", MatchKind::LeftmostFirst).find("samwise"),
+            Some(0..7)
+        );
+        // A later start never wins over an earlier one, however long.
+        assert_eq!(
+            searcher("wise|amwise", MatchKind::LeftmostLongest).find("samwise"),
+            Some(1..7)
+        );
+    }
+
+    #[test]
+    fn greedy_repetitions_and_ordered_alternations() {
+        assert_eq!(
+            searcher("a+", MatchKind::LeftmostFirst).find("xaaay"),
+            Some(1..4)
+        );
+        assert_eq!(
+            searcher("(a|ab)(c|bcd)", MatchKind::LeftmostFirst).find("abcd"),
+            Some(0..4)
+        );
+        assert_eq!(
+            searcher("(a|ab)(c|bcd)", MatchKind::LeftmostLongest).find("abcd"),
+            Some(0..4)
+        );
+        assert_eq!(
+            searcher("a|ab", MatchKind::LeftmostFirst).find("ab"),
+            Some(0..1)
+        );
+    }
+
+    #[test]
+    fn no_match() {
+        assert_eq!(searcher("ab", MatchKind::LeftmostFirst).find("xyz"), None);
+        assert_eq!(all("ab", MatchKind::LeftmostLongest, "aabb"), vec![1..3]);
+        assert_eq!(all("ab", MatchKind::LeftmostFirst, ""), vec![]);
+    }
+
+    #[test]
+    fn offsets_are_bytes() {
+        assert_eq!(all("b", MatchKind::LeftmostFirst, "éb"), vec![2..3]);
+        assert_eq!(all("a?", MatchKind::LeftmostFirst, "é"), vec![0..0, 2..2]);
+    }
+
+    // Accepting states of the other constructions have transitions out, which the match must keep following.
+    #[test]
+    fn leftmost_first_is_greedy_on_other_constructions() {
+        for construction in [
+            Construction::Thompson,
+            Construction::Glushkov,
+            Construction::Derivatives,
+            Construction::Followpos,
+        ] {
+            let fa = parse_with("a+", construction).unwrap();
+            let searcher = Searcher::new(&fa, MatchKind::LeftmostFirst);
+            assert_eq!(searcher.find("xaaay"), Some(1..4));
+            let fa = parse_with("(ab)*", construction).unwrap();
+            let searcher = Searcher::new(&fa, MatchKind::LeftmostFirst);
+            assert_eq!(searcher.find("ababa"), Some(0..4));
+        }
+    }
+}
@@ -0,0 +1,339 @@
+// Rewrites a regex into a smaller one for the same language.  Alternations and concatenations are flattened into lists
+// so the rules can see every operand at once:
+//
+// x|x = x, ε|x = x?, x|x* = x*, a|b|c = [abc]
+// ab|ac = a(b|c), ac|bc = (a|b)c
+// x*x* = x*, xx* = x*x = x+, x?x* = x*
+// (x*)* = (x+)* = (x?)* = x*, x? = x when x matches ε
+
+use crate::symbol::Symbol;
+use crate::thompsons::Expr;
+
+// Applies the rewrite rules until none of them changes the expression.
+pub fn simplify(e: Expr) -> Expr {
+    let mut e = e;
+    loop {
+        let next = rewrite(e.clone());
+        if next == e {
+            return e;
+        }
+        e = next;
+    }
+}
+
+// One pass over the expression, simplifying the operands before the operator.
+fn rewrite(e: Expr) -> Expr {
+    match e {
+        Expr::Just(Symbol::Empty) => Expr::Empty,
+        Expr::Class(class) => class_of(class),
+        // Groups only matter to captures, not to the language.
+        Expr::Group(_, e) => rewrite(*e),
+        e @ Expr::Or(..) => or_all(alternatives(e).into_iter().map(rewrite).collect()),
+        e @ Expr::And(..) => concat_all(sequence(e).into_iter().map(rewrite).collect()),
+        Expr::Star(e) => star(rewrite(*e)),
+        Expr::Plus(e) => plus(rewrite(*e)),
+        Expr::QMark(e) => qmark(rewrite(*e)),
+        Expr::Intersect(e1, e2) => {
+            let (e1, e2) = (rewrite(*e1), rewrite(*e2));
+            if e1 == e2 {
+                e1
+            } else {
+                Expr::Intersect(Box::new(e1), Box::new(e2))
+            }
+        }
+        Expr::Complement(e) => match rewrite(*e) {
+            Expr::Complement(e) => *e,
+            e => Expr::Complement(Box::new(e)),
+        },
+        e => e,
+    }
+}
+
+pub fn star(e: Expr) -> Expr {
+    match e {
+        Expr::Empty => Expr::Empty,
+        Expr::Star(e) | Expr::Plus(e) | Expr::QMark(e) => Expr::Star(e),
+        e => Expr::Star(Box::new(e)),
+    }
+}
+
+pub fn plus(e: Expr) -> Expr {
+    match e {
+        Expr::Empty => Expr::Empty,
+        e @ Expr::Plus(_) => e,
+        // One or more of something that can be empty is the same as any number of it.
+        e if e.nullable() => star(e),
+        e => Expr::Plus(Box::new(e)),
+    }
+}
+
+pub fn qmark(e: Expr) -> Expr {
+    match e {
+        e if e.nullable() => e,
+        Expr::Plus(e) => Expr::Star(e),
+        e => Expr::QMark(Box::new(e)),
+    }
+}
+
+// A class of one character is just that character.
+fn class_of(class: Vec<char>) -> Expr {
+    if class.len() == 1 {
+        Expr::Just(Symbol::Char(class[0]))
+    } else {
+        Expr::Class(class)
+    }
+}
+
+// The operands of a chain of alternations, in order.
+fn alternatives(e: Expr) -> Vec<Expr> {
+    match e {
+        Expr::Or(e1, e2) => {
+            let mut alts = alternatives(*e1);
+            alts.extend(alternatives(*e2));
+            alts
+        }
+        e => vec![e],
+    }
+}
+
+// The operands of a chain of concatenations, in order, leaving out the empty strings.
+fn sequence(e: Expr) -> Vec<Expr> {
+    match e {
+        Expr::And(e1, e2) => {
+            let mut seq = sequence(*e1);
+            seq.extend(sequence(*e2));
+            seq
+        }
+        Expr::Empty | Expr::Just(Symbol::Empty) => Vec::new(),
+        e => vec![e],
+    }
+}
+
+// Joins operands back up, leaning left like the parser does.
+fn from_alternatives(alts: Vec<Expr>) -> Expr {
+    alts.into_iter()
+        .reduce(|e1, e2| Expr::Or(Box::new(e1), Box::new(e2)))
+        .unwrap_or(Expr::Empty)
+}
+
+fn from_sequence(seq: Vec<Expr>) -> Expr {
+    seq.into_iter()
+        .reduce(|e1, e2| Expr::And(Box::new(e1), Box::new(e2)))
+        .unwrap_or(Expr::Empty)
+}
+
+fn concat_all(items: Vec<Expr>) -> Expr {
+    let mut seq: Vec<Expr> = Vec::new();
+    for e in items.into_iter().flat_map(sequence) {
+        // xy(xy)* = (xy)+, for repeats of more than one operand.
+        if let Expr::Star(x) = &e {
+            let body = sequence((**x).clone());
+            if body.len() > 1 && seq.ends_with(&body) {
+                seq.truncate(seq.len() - body.len());
+                seq.push(Expr::Plus(x.clone()));
+                continue;
+            }
+        }
+        match seq.pop() {
+            Some(prev) => seq.extend(merge_adjacent(prev, e)),
+            None => seq.push(e),
+        }
+    }
+    from_sequence(seq)
+}
+
+// Two operands next to each other in a concatenation, merged into one where a rule allows it.
+fn merge_adjacent(prev: Expr, next: Expr) -> Vec<Expr> {
+    match (prev, next) {
+        (Expr::Star(x), Expr::Star(y))
+        | (Expr::Star(x), Expr::QMark(y))
+        | (Expr::QMark(x), Expr::Star(y))
+            if x == y =>
+        {
+            vec![Expr::Star(x)]
+        }
+        (Expr::Star(x), Expr::Plus(y))
+        | (Expr::Plus(x), Expr::Star(y))
+        | (Expr::Plus(x), Expr::QMark(y))
+        | (Expr::QMark(x), Expr::Plus(y))
+            if x == y =>
+        {
+            vec![Expr::Plus(x)]
+        }
+        (x, Expr::Star(y)) if x == *y => vec![Expr::Plus(y)],
+        (Expr::Star(x), y) if *x == y => vec![Expr::Plus(x)],
+        (prev, next) => vec![prev, next],
+    }
+}
+
+fn or_all(items: Vec<Expr>) -> Expr {
+    let mut alts: Vec<Expr> = Vec::new();
+    for e in items.into_iter().flat_map(alternatives) {
+        if !alts.contains(&e) {
+            alts.push(e);
+        }
+    }
+    let has_empty = alts.contains(&Expr::Empty);
+    alts.retain(|e| *e != Expr::Empty);
+    // Leave out any alternative whose strings another alternative already matches.
+    let alts = alts
+        .iter()
+        .filter(|&e| !alts.iter().any(|other| absorbs(other, e)))
+        .cloned()
+        .collect::<Vec<Expr>>();
+    if alts.is_empty() {
+        return Expr::Empty;
+    }
+
+    let alts = merge_chars(factor(factor(alts, true), false));
+    let e = from_alternatives(alts);
+    if has_empty {
+        qmark(e)
+    } else {
+        e
+    }
+}
+
+// Whether every string `small` matches is one `big` matches, as far as their shapes show.
+fn absorbs(big: &Expr, small: &Expr) -> bool {
+    match (big, small) {
+        (Expr::Star(x), Expr::Plus(y)) | (Expr::Star(x), Expr::QMark(y)) => x == y,
+        (Expr::Star(x), y) | (Expr::Plus(x), y) | (Expr::QMark(x), y) => **x == *y,
+        (Expr::Class(class), Expr::Just(Symbol::Char(c))) => class.contains(c),
+        _ => false,
+    }
+}
+
+// Groups the alternatives by their first operand (or their last, if `front` is false), pulling it out of each group
+// with more than one member: ab|c|ad = a(b|d)|c.  Alternation is commutative, so the groups need not be adjacent.
+fn factor(alts: Vec<Expr>, front: bool) -> Vec<Expr> {
+    let mut groups: Vec<(Expr, Vec<Vec<Expr>>)> = Vec::new();
+    for alt in alts {
+        let mut seq = sequence(alt);
+        let key = if front && !seq.is_empty() {
+            seq.remove(0)
+        } else {
+            seq.pop().unwrap_or(Expr::Empty)
+        };
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, rests)) => rests.push(seq),
+            None => groups.push((key, vec![seq])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, mut rests)| {
+            if rests.len() == 1 {
+                let mut seq = rests.pop().unwrap();
+                if front {
+                    seq.insert(0, key);
+                } else {
+                    seq.push(key);
+                }
+                return from_sequence(seq);
+            }
+            let rest = or_all(rests.into_iter().map(from_sequence).collect());
+            if front {
+                concat_all(vec![key, rest])
+            } else {
+                concat_all(vec![rest, key])
+            }
+        })
+        .collect()
+}
+
+// Merges the single characters and classes among the alternatives into one class, where the first of them was.
+// A `]` is left out, since it cannot be written inside a class.
+fn merge_chars(alts: Vec<Expr>) -> Vec<Expr> {
+    let chars_of = |e: &Expr| match e {
+        Expr::Just(Symbol::Char(c)) if *c != ']' => Some(vec![*c]),
+        Expr::Class(class) => Some(class.clone()),
+        _ => None,
+    };
+    if alts.iter().filter(|e| chars_of(e).is_some()).count() < 2 {
+        return alts;
+    }
+
+    let mut class = alts
+        .iter()
+        .filter_map(chars_of)
+        .flatten()
+        .collect::<Vec<char>>();
+    class.sort_unstable();
+    class.dedup();
+    let mut class = Some(class_of(class));
+    let mut merged = Vec::new();
+    for e in alts {
+        if chars_of(&e).is_none() {
+            merged.push(e);
+        } else if let Some(class) = class.take() {
+            merged.push(class);
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivalence::{equivalence, Equivalence};
+    use crate::regex_parser::parse_to_expr;
+    use crate::thompsons::expr_to_finite_automata;
+
+    // Simplifies a regex, checks that the result accepts the same language, and returns it as a string.
+    fn simplified(regex: &str) -> String {
+        let expr = parse_to_expr(regex).unwrap();
+        let simple = simplify(expr.clone());
+        assert_eq!(
+            equivalence(
+                &expr_to_finite_automata(simple.clone()),
+                &expr_to_finite_automata(expr)
+            ),
+            Equivalence::Equivalent,
+            "{} simplified to {}",
+            regex,
+            simple
+        );
+        simple.to_string()
+    }
+
+    #[test]
+    fn idempotent_alternation() {
+        assert_eq!(simplified("(a|a)"), "a");
+        assert_eq!(simplified("ab|ab|ab"), "ab");
+    }
+
+    #[test]
+    fn nested_stars() {
+        assert_eq!(simplified("(x*)*"), "x*");
+        assert_eq!(simplified("(x+)*"), "x*");
+        assert_eq!(simplified("(x?)*"), "x*");
+    }
+
+    #[test]
+    fn optional_before_star() {
+        assert_eq!(simplified("(ε|y)y*"), "y*");
+        assert_eq!(simplified("yy*"), "y+");
+    }
+
+    #[test]
+    fn common_prefix() {
+        assert_eq!(simplified("ab|ac"), "a[bc]");
+        assert_eq!(simplified("abc|abd"), "ab[cd]");
+        assert_eq!(simplified("xyz|xw"), "x(yz|w)");
+    }
+
+    #[test]
+    fn common_suffix() {
+        assert_eq!(simplified("ac|bc"), "[ab]c");
+        assert_eq!(simplified("xz|yz"), "[xy]z");
+    }
+
+    #[test]
+    fn characters_merge_into_classes() {
+        assert_eq!(simplified("a|b|c"), "[a-c]");
+        assert_eq!(simplified("a|c|e|d"), "[ac-e]");
+        assert_eq!(simplified("[ab]|c"), "[a-c]");
+    }
+}
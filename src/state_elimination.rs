@@ -0,0 +1,123 @@
+// Converts a finite automaton back into a regex by state elimination (Brzozowski and McCluskey).  Edges are labelled
+// with regexes instead of symbols, and states are removed one at a time: each path through a removed state becomes a
+// direct edge, labelled with the regex for going in, looping any number of times, and coming out.
+
+use crate::fa::{State, StateSet, FA};
+use crate::simplify::{qmark, simplify, star};
+use crate::symbol::Symbol;
+use crate::thompsons::Expr;
+
+use std::collections::BTreeMap;
+
+// Constructors that apply the simplifications cheap enough to check as the regex is built.
+fn or(e1: Expr, e2: Expr) -> Expr {
+    match (e1, e2) {
+        (e1, e2) if e1 == e2 => e1,
+        (Expr::Empty, e) | (e, Expr::Empty) => qmark(e),
+        // x?|x = x|x? = x?
+        (Expr::QMark(e1), e2) if *e1 == e2 => Expr::QMark(e1),
+        (e1, Expr::QMark(e2)) if e1 == *e2 => Expr::QMark(e2),
+        (e1, e2) => Expr::Or(Box::new(e1), Box::new(e2)),
+    }
+}
+
+fn concat(e1: Expr, e2: Expr) -> Expr {
+    match (e1, e2) {
+        (Expr::Empty, e) | (e, Expr::Empty) => e,
+        // x*x = xx* = x+
+        (Expr::Star(e1), e2) if *e1 == e2 => Expr::Plus(e1),
+        (e1, Expr::Star(e2)) if e1 == *e2 => Expr::Plus(e2),
+        // Concatenations lean left, so x x* can also hide inside (w x) x*.
+        (Expr::And(w, e1), Expr::Star(e2)) if *e1 == *e2 => concat(*w, Expr::Plus(e2)),
+        (e1, e2) => Expr::And(Box::new(e1), Box::new(e2)),
+    }
+}
+
+// Adds an edge, merging it with any edge already between the same two states.
+fn add_edge(edges: &mut BTreeMap<(State, State), Expr>, from: State, to: State, e: Expr) {
+    let merged = match edges.remove(&(from, to)) {
+        Some(existing) => or(existing, e),
+        None => e,
+    };
+    edges.insert((from, to), merged);
+}
+
+impl FA {
+    // A simplified regex for the language this automaton accepts, or `None` if it accepts nothing.
+    pub fn to_regex(&self) -> Option<Expr> {
+        // Only states on some path from the start to acceptance contribute to the language.
+        let reachable = self.reachable_states();
+        let mut remaining = self
+            .coaccessible_states()
+            .into_iter()
+            .filter(|s| reachable.contains(s))
+            .collect::<StateSet<State>>();
+        if !remaining.contains(&self.starting()) {
+            return None;
+        }
+
+        // A new start with no edges in and a new end with no edges out, so that once every old state is gone, the
+        // single edge between them carries the whole language.
+        let start = remaining.iter().max().unwrap() + 1;
+        let end = start + 1;
+        let mut edges = BTreeMap::new();
+        add_edge(&mut edges, start, self.starting(), Expr::Empty);
+        for t in self.delta() {
+            if remaining.contains(&t.start()) && remaining.contains(&t.end()) {
+                let e = match t.sym() {
+                    Symbol::Empty => Expr::Empty,
+                    sym => Expr::Just(sym),
+                };
+                add_edge(&mut edges, t.start(), t.end(), e);
+            }
+        }
+        for &a in self.accepting() {
+            if remaining.contains(&a) {
+                add_edge(&mut edges, a, end, Expr::Empty);
+            }
+        }
+
+        while !remaining.is_empty() {
+            // Remove the state with the fewest paths through it first, which keeps the regexes on the edges small.
+            let &q = remaining
+                .iter()
+                .min_by_key(|&&q| {
+                    let ins = edges.keys().filter(|&&(p, r)| r == q && p != q).count();
+                    let outs = edges.keys().filter(|&&(p, r)| p == q && r != q).count();
+                    ins * outs
+                })
+                .unwrap();
+            remaining.remove(&q);
+
+            let repeat = edges.remove(&(q, q)).map(star);
+            let ins = edges
+                .keys()
+                .filter(|&&(_, r)| r == q)
+                .copied()
+                .collect::<Vec<(State, State)>>()
+                .into_iter()
+                .map(|key| (key.0, edges.remove(&key).unwrap()))
+                .collect::<Vec<(State, Expr)>>();
+            let outs = edges
+                .keys()
+                .filter(|&&(p, _)| p == q)
+                .copied()
+                .collect::<Vec<(State, State)>>()
+                .into_iter()
+                .map(|key| (key.1, edges.remove(&key).unwrap()))
+                .collect::<Vec<(State, Expr)>>();
+
+            for (p, e_in) in &ins {
+                for (r, e_out) in &outs {
+                    let mut path = e_in.clone();
+                    if let Some(repeat) = &repeat {
+                        path = concat(path, repeat.clone());
+                    }
+                    add_edge(&mut edges, *p, *r, concat(path, e_out.clone()));
+                }
+            }
+        }
+
+        edges.remove(&(start, end)).map(simplify)
+    }
+}
@@ -0,0 +1,230 @@
+// A record of the subset construction, step by step, for following it by hand.  Each step marks one DFA state: a set
+// of NFA states, taken in the order they were found.  For each character, it moves the set along the character's
+// transitions, takes the ε-closure of the result, and adds that as a new DFA state if it was not found before.
+//
+// DFA states are named A, B, C and so on in the order they were found, as in the Dragon Book (section 3.7.1), and
+// state A is state 0 of the DFA that `dfa_from` builds, B is state 1, and so on.
+
+use crate::fa::{subset_label, State, StateSet};
+
+use std::fmt::{self, Write};
+
+#[derive(Debug, Clone)]
+pub struct SubsetMove {
+    pub sym: char,
+    // The NFA states the marked set reaches on `sym`, before the ε-closure.
+    pub moved: StateSet<State>,
+    pub closure: StateSet<State>,
+    // Whether the closure was a DFA state not found before.
+    pub new: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubsetStep {
+    // The DFA state marked in this step.
+    pub set: StateSet<State>,
+    // A move for each character the set has transitions on.
+    pub moves: Vec<SubsetMove>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubsetTrace {
+    pub alphabet: Vec<char>,
+    // The NFA's accepting states.
+    pub accepting: StateSet<State>,
+    pub steps: Vec<SubsetStep>,
+}
+
+// A, B, ..., Z, then AA, AB and so on.
+fn name(mut i: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push((b'A' + (i % 26) as u8) as char);
+        if i < 26 {
+            break;
+        }
+        i = i / 26 - 1;
+    }
+    name.iter().rev().collect()
+}
+
+// Characters in markdown code spans, with a pipe escaped so it does not split the table cell.
+fn code(c: char) -> String {
+    match c {
+        '|' => "`\\|`".to_string(),
+        '`' => "`` ` ``".to_string(),
+        c => format!("`{}`", c),
+    }
+}
+
+impl SubsetTrace {
+    // The name of the DFA state for a set of NFA states.
+    fn name_of(&self, set: &StateSet<State>) -> String {
+        self.steps
+            .iter()
+            .position(|step| step.set == *set)
+            .map_or_else(|| "?".to_string(), name)
+    }
+    // Dtran: the DFA state each DFA state goes to on each character, or `-` for none.
+    fn dtran(&self, step: &SubsetStep) -> Vec<String> {
+        self.alphabet
+            .iter()
+            .map(|&c| match step.moves.iter().find(|m| m.sym == c) {
+                Some(m) => self.name_of(&m.closure),
+                None => "-".to_string(),
+            })
+            .collect()
+    }
+    fn accepting_names(&self) -> Vec<String> {
+        (0..self.steps.len())
+            .filter(|&i| !self.steps[i].set.is_disjoint(&self.accepting))
+            .map(name)
+            .collect()
+    }
+    // Each step as plain text, then the table of Dstates and Dtran.
+    pub fn text(&self) -> Result<String, fmt::Error> {
+        let mut output = String::new();
+
+        for (i, step) in self.steps.iter().enumerate() {
+            writeln!(output, "mark {} = {}", name(i), subset_label(&step.set))?;
+            for m in &step.moves {
+                writeln!(
+                    output,
+                    "  {:?}: move {}, ε-closure {} = {}{}",
+                    m.sym,
+                    subset_label(&m.moved),
+                    subset_label(&m.closure),
+                    self.name_of(&m.closure),
+                    if m.new { ", new" } else { "" }
+                )?;
+            }
+        }
+        writeln!(output)?;
+
+        let mut rows = vec![std::iter::once("NFA states".to_string())
+            .chain(std::iter::once("DFA state".to_string()))
+            .chain(self.alphabet.iter().map(|c| c.to_string()))
+            .collect::<Vec<String>>()];
+        for (i, step) in self.steps.iter().enumerate() {
+            let mut row = vec![subset_label(&step.set), name(i)];
+            row.extend(self.dtran(step));
+            rows.push(row);
+        }
+        let widths = (0..rows[0].len())
+            .map(|k| {
+                rows.iter()
+                    .map(|row| row[k].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<usize>>();
+        for row in &rows {
+            let cells = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<String>>();
+            writeln!(output, "{}", cells.join("  ").trim_end())?;
+        }
+        writeln!(output, "accepting: {}", self.accepting_names().join(", "))?;
+
+        Ok(output)
+    }
+    // The same as `text`, as a markdown list of steps and a markdown table.
+    pub fn markdown(&self) -> Result<String, fmt::Error> {
+        let mut output = String::new();
+
+        for (i, step) in self.steps.iter().enumerate() {
+            writeln!(
+                output,
+                "{}. Mark {} = `{}`",
+                i + 1,
+                name(i),
+                subset_label(&step.set)
+            )?;
+            for m in &step.moves {
+                writeln!(
+                    output,
+                    "   - {}: move = `{}`, ε-closure = `{}` = {}{}",
+                    code(m.sym),
+                    subset_label(&m.moved),
+                    subset_label(&m.closure),
+                    self.name_of(&m.closure),
+                    if m.new { " (new)" } else { "" }
+                )?;
+            }
+        }
+        writeln!(output)?;
+
+        let row = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+        let mut header = vec!["NFA states".to_string(), "DFA state".to_string()];
+        header.extend(self.alphabet.iter().map(|&c| code(c)));
+        writeln!(output, "{}", row(header.clone()))?;
+        writeln!(output, "|{}", "---|".repeat(header.len()))?;
+        for (i, step) in self.steps.iter().enumerate() {
+            let mut cells = vec![format!("`{}`", subset_label(&step.set)), name(i)];
+            cells.extend(self.dtran(step));
+            writeln!(output, "{}", row(cells))?;
+        }
+        writeln!(output)?;
+        writeln!(output, "Accepting: {}", self.accepting_names().join(", "))?;
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex_parser::parse_to_nfa;
+    use crate::symbol::Symbol;
+
+    // Dtran for (a|b)*abb, as in the Dragon Book's figure 3.36.
+    const DTRAN: [(&str, &str, &str); 5] = [
+        ("A", "B", "C"),
+        ("B", "B", "D"),
+        ("C", "B", "C"),
+        ("D", "B", "E"),
+        ("E", "B", "C"),
+    ];
+
+    // The NFA's state numbers depend on what else has been built, so the table is checked from its second column on.
+    #[test]
+    fn dragon_book_table() {
+        let nfa = parse_to_nfa("(a|b)*abb").unwrap();
+        let text = nfa.subset_trace().text().unwrap();
+        let mut table = text
+            .lines()
+            .skip_while(|line| !line.starts_with("NFA states"));
+        assert!(table.next().unwrap().ends_with("DFA state  a  b"));
+        let rows = table
+            .map(|line| line.split_whitespace().skip(1).collect::<Vec<&str>>())
+            .collect::<Vec<_>>();
+        let mut expected = DTRAN
+            .iter()
+            .map(|&(from, on_a, on_b)| vec![from, on_a, on_b])
+            .collect::<Vec<_>>();
+        expected.push(vec!["E"]);
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn names_follow_the_dfa_numbering() {
+        let nfa = parse_to_nfa("(a|b)*abb").unwrap();
+        let trace = nfa.subset_trace();
+        let dfa = nfa.dfa_from();
+        let names = DTRAN.iter().map(|row| row.0).collect::<Vec<&str>>();
+        assert_eq!(trace.steps[0].set, nfa.epsilon_closure(nfa.starting()));
+        assert_eq!(dfa.starting(), 0);
+        for (state, &(from, on_a, on_b)) in DTRAN.iter().enumerate() {
+            assert_eq!(name(state), from);
+            assert_eq!(dfa.subset_of(state), Some(&trace.steps[state].set));
+            for (c, to) in [('a', on_a), ('b', on_b)] {
+                let next = dfa.next_state(state, c).unwrap();
+                assert_eq!(names[next], to);
+            }
+        }
+        assert_eq!(dfa.accepting(), &vec![4]);
+        assert!(dfa.delta().iter().all(|t| t.sym() != Symbol::Empty));
+    }
+}
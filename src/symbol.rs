@@ -1,16 +1,19 @@
-
-#[derive(Eq, PartialEq, Hash, Debug, Clone, Copy)]
-pub enum Symbol {
-    Empty,
-    Char(char),
-}
-
-
-pub const ASCII: [char; 95] = [' ', '!', '"', '#', '$', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?', '@', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '[', '\\', ']', '^', '_', '`', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '{', '|', '}', '~'];
-
-
-
-
-// Ranges? We'd rewrite Symbol so that Symbol::Char was just Symbol::Range.  Or, perhaps we'd
-// merely extend Symbol with the Range(Range) item?
-
+
+#[derive(Eq, PartialEq, Hash, Debug, Clone, Copy)]
+pub enum Symbol {
+    Empty,
+    Char(char),
+}
+
+
+// Written where a regex or a drawing needs to show the empty string.
+pub const EPSILON: char = '\u{03B5}';
+
+pub const ASCII: [char; 95] = [' ', '!', '"', '#', '$', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?', '@', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '[', '\\', ']', '^', '_', '`', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '{', '|', '}', '~'];
+
+
+
+
+// Ranges? We'd rewrite Symbol so that Symbol::Char was just Symbol::Range.  Or, perhaps we'd
+// merely extend Symbol with the Range(Range) item?
+
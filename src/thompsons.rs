@@ -1,352 +1,811 @@
-// thompson's construction
-
-use crate::fa::{State, StateSet, FA};
-use crate::symbol::{Symbol, ASCII};
-use crate::transition::Transition;
-use std::sync::atomic::{AtomicUsize, Ordering};
-// use std::fmt;
-
-use crate::regex_parser::OPERATORS;
-
-// * A special FA with only one start and end state pair.
-// TODO: ! Change Vec<state> to StateSet<State>.
-#[derive(Debug, Clone)]
-struct FAPiece {
-    states: StateSet<State>,
-    start: State,
-    end: State,
-    delta: Vec<Transition>,
-}
-
-// !! Or, have FAPiece be an ENUM of the various thompson's pieces!!  have each individual thompson's piece be a struct on its own right!  Have each part point to inner parts.
-
-impl FAPiece {
-    pub fn new() -> Self {
-        Self {
-            states: StateSet::new(),
-            start: 0,
-            end: 0,
-            delta: Vec::new(),
-        }
-    }
-    pub fn produce_id() -> State {
-        static COUNTER: AtomicUsize = AtomicUsize::new(0);
-        COUNTER.fetch_add(1, Ordering::Relaxed)
-    }
-    pub fn num_states(&self) -> usize {
-        self.states.len()
-    }
-    pub fn set_start(&mut self, s: State) {
-        self.start = s;
-    }
-    pub fn set_end(&mut self, s: State) {
-        self.end = s;
-    }
-    pub fn add_state(&mut self, s: State) {
-        self.states.insert(s);
-    }
-    pub fn add_transition(&mut self, t: Transition) {
-        self.delta.push(t);
-    }
-    pub fn remove_state(&mut self, s: State) {
-        self.states.remove(&s);
-    }
-    pub fn states(&self) -> StateSet<State> {
-        self.states.clone()
-    }
-    pub fn start(&self) -> State {
-        self.start
-    }
-    pub fn end(&self) -> State {
-        self.end
-    }
-    pub fn delta(&self) -> &Vec<Transition> {
-        &self.delta
-    }
-    pub fn delta_mut(&mut self) -> &mut Vec<Transition> {
-        &mut self.delta
-    }
-    pub fn just_sym(sym: Symbol) -> Self {
-        let mut just_piece = FAPiece::new();
-
-        let start = Self::produce_id();
-        let end = Self::produce_id();
-
-        just_piece.add_state(start);
-        just_piece.add_state(end);
-        just_piece.set_start(start);
-        just_piece.set_end(end);
-
-        just_piece.add_transition(Transition::from(sym, start, end));
-
-        return just_piece;
-    }
-    pub fn new_with_start_end(start: State, end: State) -> Self {
-        let mut states = StateSet::new();
-        states.insert(start);
-        states.insert(end);
-        FAPiece {
-            states,
-            start,
-            end,
-            delta: Vec::new(),
-        }
-    }
-}
-
-// Define expression.
-// Btw, this definition seems robust enough.  See https://rust-unofficial.github.io/patterns/patterns/behavioural/visitor.html; they do pretty much the same thing.
-#[derive(Debug, Clone)]
-enum Expr {
-    Empty,
-    Just(Symbol),
-    Or(Box<Expr>, Box<Expr>),
-    And(Box<Expr>, Box<Expr>),
-    Star(Box<Expr>),
-    Plus(Box<Expr>),
-    QMark(Box<Expr>),
-}
-
-fn parse_string_to_expr(s: &String) -> Option<Expr> {
-    let mut expstack: Vec<Expr> = Vec::new();
-    for c in s.chars() {
-        match c {
-            '.' => {
-                // ! The expression popped second comes first in the chronological order.
-                let sym2 = expstack.pop()?;
-                let sym1 = expstack.pop()?;
-                expstack.push(Expr::And(Box::new(sym1), Box::new(sym2)));
-            }
-            '|' => {
-                let sym1 = expstack.pop()?;
-                let sym2 = expstack.pop()?;
-                expstack.push(Expr::Or(Box::new(sym1), Box::new(sym2)));
-            }
-            '*' => {
-                if let Some(sym) = expstack.pop() {
-                    expstack.push(Expr::Star(Box::new(sym)));
-                } else {
-                    eprintln!("Missing an expr from the stack.  Again, this is the Star branch of the match.");
-                }
-            }
-            '+' => {
-                if let Some(sym) = expstack.pop() {
-                    expstack.push(Expr::Plus(Box::new(sym)));
-                } else {
-                    eprintln!("Missing an expr from the stack.  Again, this is the Plus branch of the match.");
-                }
-            }
-            '?' => {
-                if let Some(sym) = expstack.pop() {
-                    expstack.push(Expr::QMark(Box::new(sym)));
-                } else {
-                    eprintln!("Missing an expr from the stack.  Again, this is the QMark branch of the match.");
-                }
-            }
-            c if ASCII.contains(&c) => {
-                expstack.push(Expr::Just(Symbol::Char(c)));
-            }
-            _ => unreachable!("Illegal character"),
-        }
-    }
-    // The last expr on expstack is the expression we want.
-    // If not, then expr parse error.
-    if expstack.len() != 1 {
-        None
-    } else {
-        expstack.pop()
-    }
-}
-
-// Parse an Expression into a recursive set of FAPieces.
-// ! Super inefficient... it just reads redundant transitions over and over again.  STOP CREATING NEW FAPIECES!  JUST USE THE OLD ONES!
-// DETERMINE A WAY TO CONSTANT TIME APPEND STATES AND TRANSITIONS, INSTEAD OF ITERATING
-fn parse(expr: Expr) -> FAPiece {
-    // Match on an expression, turning it into a single finite automata.
-    // This is done by recursing through the expression and building the piece bit by bit.
-    match expr {
-        Expr::Empty => FAPiece::just_sym(Symbol::Empty),
-        Expr::Just(sym) => FAPiece::just_sym(sym),
-        Expr::Or(e1, e2) => {
-            let fa_piece1 = parse(*e1);
-            let fa_piece2 = parse(*e2);
-
-            let start = FAPiece::produce_id();
-            let end = FAPiece::produce_id();
-
-            let mut or_piece = FAPiece::new_with_start_end(start, end);
-
-            fa_piece1
-                .states()
-                .iter()
-                .for_each(|&state| or_piece.add_state(state));
-            fa_piece2
-                .states()
-                .iter()
-                .for_each(|&state| or_piece.add_state(state));
-            fa_piece1
-                .delta()
-                .iter()
-                .for_each(|&trans| or_piece.add_transition(trans));
-            fa_piece2
-                .delta()
-                .iter()
-                .for_each(|&trans| or_piece.add_transition(trans));
-
-            or_piece.add_transition(Transition::from(Symbol::Empty, start, fa_piece1.start()));
-            or_piece.add_transition(Transition::from(Symbol::Empty, start, fa_piece2.start()));
-            or_piece.add_transition(Transition::from(Symbol::Empty, fa_piece1.end(), end));
-            or_piece.add_transition(Transition::from(Symbol::Empty, fa_piece2.end(), end));
-
-            or_piece
-        }
-        Expr::And(e1, e2) => {
-            let fa_piece1 = parse(*e1);
-            let mut fa_piece2 = parse(*e2);
-
-            let mut and_piece = FAPiece::new_with_start_end(fa_piece1.start(), fa_piece2.end());
-
-            // Connect the two pieces, removing old states and adding new states and adjusting transiitons.
-            let oldstart = fa_piece2.start();
-            let newstart = fa_piece1.end();
-
-            fa_piece2.remove_state(fa_piece2.start());
-            fa_piece2.add_state(fa_piece1.end());
-            fa_piece2.set_start(fa_piece1.end());
-
-            // Change all the transitions so that they use the new starting state.
-            fa_piece2.delta_mut().iter_mut().for_each(|trans| {
-                if trans.start() == oldstart {
-                    trans.set_start(newstart);
-                } else if trans.end() == oldstart {
-                    trans.set_end(newstart)
-                }
-            });
-
-            fa_piece1
-                .states()
-                .iter()
-                .for_each(|&state| and_piece.add_state(state));
-            fa_piece2
-                .states()
-                .iter()
-                .for_each(|&state| and_piece.add_state(state));
-            fa_piece1
-                .delta()
-                .iter()
-                .for_each(|&trans| and_piece.add_transition(trans));
-            fa_piece2
-                .delta()
-                .iter()
-                .for_each(|&trans| and_piece.add_transition(trans));
-
-            and_piece
-        }
-        Expr::Star(e) => {
-            let fa_piece = parse(*e);
-
-            let start = FAPiece::produce_id();
-            let end = FAPiece::produce_id();
-
-            let mut star_piece = FAPiece::new_with_start_end(start, end);
-
-            fa_piece
-                .states()
-                .iter()
-                .for_each(|&state| star_piece.add_state(state));
-            fa_piece
-                .delta()
-                .iter()
-                .for_each(|&trans| star_piece.add_transition(trans));
-
-            star_piece.add_transition(Transition::from(Symbol::Empty, start, fa_piece.start()));
-            star_piece.add_transition(Transition::from(Symbol::Empty, fa_piece.end(), end));
-            star_piece.add_transition(Transition::from(Symbol::Empty, start, end));
-            star_piece.add_transition(Transition::from(
-                Symbol::Empty,
-                fa_piece.end(),
-                fa_piece.start(),
-            ));
-
-            star_piece
-        }
-        Expr::Plus(e) => {
-            let fa_piece = parse(*e);
-
-            let start = FAPiece::produce_id();
-            let end = FAPiece::produce_id();
-
-            let mut plus_piece = FAPiece::new_with_start_end(start, end);
-
-            fa_piece
-                .states()
-                .iter()
-                .for_each(|&state| plus_piece.add_state(state));
-            fa_piece
-                .delta()
-                .iter()
-                .for_each(|&trans| plus_piece.add_transition(trans));
-
-            plus_piece.add_transition(Transition::from(Symbol::Empty, start, fa_piece.start()));
-            plus_piece.add_transition(Transition::from(Symbol::Empty, fa_piece.end(), end));
-            // plus_piece.add_transition(Transition::from(Symbol::Empty, start, end));
-            plus_piece.add_transition(Transition::from(
-                Symbol::Empty,
-                fa_piece.end(),
-                fa_piece.start(),
-            ));
-
-            plus_piece
-        }
-        Expr::QMark(e) => {
-            let fa_piece = parse(*e);
-
-            let start = FAPiece::produce_id();
-            let end = FAPiece::produce_id();
-
-            let mut qmark_piece = FAPiece::new_with_start_end(start, end);
-
-            fa_piece
-                .states()
-                .iter()
-                .for_each(|&state| qmark_piece.add_state(state));
-            fa_piece
-                .delta()
-                .iter()
-                .for_each(|&trans| qmark_piece.add_transition(trans));
-
-            qmark_piece.add_transition(Transition::from(Symbol::Empty, start, fa_piece.start()));
-            qmark_piece.add_transition(Transition::from(Symbol::Empty, fa_piece.end(), end));
-            qmark_piece.add_transition(Transition::from(Symbol::Empty, start, end));
-            // qmark_piece.add_transition(Transition::from(
-            //     Symbol::Empty,
-            //     fa_piece.end(),
-            //     fa_piece.start(),
-            // ));
-
-            qmark_piece
-        }
-    }
-}
-
-fn fapiece_to_fa(construction: FAPiece) -> FA {
-    let mut fa = FA::new();
-
-    for state in construction.states().clone().into_iter() {
-        fa.add_state(state);
-    }
-    fa.set_start(construction.start());
-    fa.add_acceptor(construction.end());
-    for transition in construction.delta() {
-        fa.add_transition(*transition);
-    }
-
-    fa
-}
-
-pub fn parse_to_finite_automata(input: &String) -> Option<FA> {
-    let expr = parse_string_to_expr(input)?;
-    let fa_piece = parse(expr);
-    let fa = fapiece_to_fa(fa_piece);
-    Some(fa)
-}
+// thompson's construction
+
+use crate::fa::{State, StateSet, FA};
+use crate::regex_parser::OPERATORS;
+use crate::symbol::{Symbol, ASCII, EPSILON};
+use crate::transition::Transition;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+// use std::fmt;
+
+
+// * A special FA with only one start and end state pair.
+// TODO: ! Change Vec<state> to StateSet<State>.
+#[derive(Debug, Clone)]
+struct FAPiece {
+    states: StateSet<State>,
+    start: State,
+    end: State,
+    delta: Vec<Transition>,
+}
+
+// !! Or, have FAPiece be an ENUM of the various thompson's pieces!!  have each individual thompson's piece be a struct on its own right!  Have each part point to inner parts.
+
+impl FAPiece {
+    pub fn new() -> Self {
+        Self {
+            states: StateSet::new(),
+            start: 0,
+            end: 0,
+            delta: Vec::new(),
+        }
+    }
+    pub fn produce_id() -> State {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    }
+    pub fn num_states(&self) -> usize {
+        self.states.len()
+    }
+    pub fn set_start(&mut self, s: State) {
+        self.start = s;
+    }
+    pub fn set_end(&mut self, s: State) {
+        self.end = s;
+    }
+    pub fn add_state(&mut self, s: State) {
+        self.states.insert(s);
+    }
+    pub fn add_transition(&mut self, t: Transition) {
+        self.delta.push(t);
+    }
+    pub fn remove_state(&mut self, s: State) {
+        self.states.remove(&s);
+    }
+    pub fn states(&self) -> StateSet<State> {
+        self.states.clone()
+    }
+    pub fn start(&self) -> State {
+        self.start
+    }
+    pub fn end(&self) -> State {
+        self.end
+    }
+    pub fn delta(&self) -> &Vec<Transition> {
+        &self.delta
+    }
+    pub fn delta_mut(&mut self) -> &mut Vec<Transition> {
+        &mut self.delta
+    }
+    pub fn just_sym(sym: Symbol) -> Self {
+        let mut just_piece = FAPiece::new();
+
+        let start = Self::produce_id();
+        let end = Self::produce_id();
+
+        just_piece.add_state(start);
+        just_piece.add_state(end);
+        just_piece.set_start(start);
+        just_piece.set_end(end);
+
+        just_piece.add_transition(Transition::from(sym, start, end));
+
+        just_piece
+    }
+    pub fn new_with_start_end(start: State, end: State) -> Self {
+        let mut states = StateSet::new();
+        states.insert(start);
+        states.insert(end);
+        FAPiece {
+            states,
+            start,
+            end,
+            delta: Vec::new(),
+        }
+    }
+}
+
+// Define expression.
+// Btw, this definition seems robust enough.  See https://rust-unofficial.github.io/patterns/patterns/behavioural/visitor.html; they do pretty much the same thing.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Expr {
+    Empty,
+    Just(Symbol),
+    // A bracketed set of characters, like `[a-z_]`, kept sorted and without repeats.
+    Class(Vec<char>),
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Star(Box<Expr>),
+    Plus(Box<Expr>),
+    QMark(Box<Expr>),
+    Intersect(Box<Expr>, Box<Expr>),
+    Complement(Box<Expr>),
+    // A parenthesized subexpression, which captures what it matches.  Groups are numbered from 1 in the order of
+    // their opening parentheses, and `(?<name>...)` names one too.
+    Group(Option<String>, Box<Expr>),
+}
+
+impl Expr {
+    // How tightly the outermost operator binds, by the same precedences the parser uses.  Symbols bind tightest.
+    fn precedence(&self) -> usize {
+        match self {
+            Expr::Empty | Expr::Just(_) | Expr::Class(_) | Expr::Group(..) => OPERATORS[&'*'] + 1,
+            Expr::Or(..) => OPERATORS[&'|'],
+            Expr::And(..) => OPERATORS[&'.'],
+            Expr::Star(_) | Expr::Plus(_) | Expr::QMark(_) => OPERATORS[&'*'],
+            Expr::Intersect(..) => OPERATORS[&'&'],
+            Expr::Complement(_) => OPERATORS[&'~'],
+        }
+    }
+    // Whether the expression matches the empty string.
+    pub fn nullable(&self) -> bool {
+        match self {
+            Expr::Empty | Expr::Just(Symbol::Empty) => true,
+            Expr::Just(_) | Expr::Class(_) => false,
+            Expr::Or(e1, e2) => e1.nullable() || e2.nullable(),
+            Expr::And(e1, e2) | Expr::Intersect(e1, e2) => e1.nullable() && e2.nullable(),
+            Expr::Star(_) | Expr::QMark(_) => true,
+            Expr::Plus(e) | Expr::Group(_, e) => e.nullable(),
+            Expr::Complement(e) => !e.nullable(),
+        }
+    }
+}
+
+// Reads the characters of a class up to its closing bracket.  `a-z` stands for every character from `a` to `z`; a `-`
+// at either end of the class is taken literally.
+fn parse_class(chars: &mut impl Iterator<Item = char>) -> Option<Vec<char>> {
+    let mut members = Vec::new();
+    loop {
+        match chars.next()? {
+            ']' => break,
+            c => members.push(c),
+        }
+    }
+    let mut class = Vec::new();
+    let mut i = 0;
+    while i < members.len() {
+        if i + 2 < members.len() && members[i + 1] == '-' {
+            if members[i] > members[i + 2] {
+                return None;
+            }
+            class.extend(members[i]..=members[i + 2]);
+            i += 3;
+        } else {
+            class.push(members[i]);
+            i += 1;
+        }
+    }
+    class.sort_unstable();
+    class.dedup();
+    Some(class)
+}
+
+// Writes a class's characters back out, with runs of three or more as ranges.  A `-` goes first, where it is literal.
+fn write_class(f: &mut std::fmt::Formatter, class: &[char]) -> std::fmt::Result {
+    write!(f, "[")?;
+    if class.contains(&'-') {
+        write!(f, "-")?;
+    }
+    let rest = class.iter().filter(|&&c| c != '-').copied().collect::<Vec<char>>();
+    let mut i = 0;
+    while i < rest.len() {
+        let mut j = i;
+        while j + 1 < rest.len() && rest[j + 1] as u32 == rest[j] as u32 + 1 {
+            j += 1;
+        }
+        if j - i >= 2 {
+            write!(f, "{}-{}", rest[i], rest[j])?;
+        } else {
+            rest[i..=j].iter().try_for_each(|c| write!(f, "{}", c))?;
+        }
+        i = j + 1;
+    }
+    write!(f, "]")
+}
+
+// Whether a character means something other than itself outside of a class.
+fn is_special(c: char) -> bool {
+    OPERATORS.contains_key(&c) || matches!(c, '(' | ')' | '[' | '\\' | EPSILON)
+}
+
+// Writes the expression back out in regex syntax, with only the parentheses the precedences require.
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Writes a subexpression, parenthesized if it binds looser than `min`.
+        let sub = |f: &mut std::fmt::Formatter, e: &Expr, min: usize| {
+            if e.precedence() < min {
+                write!(f, "({})", e)
+            } else {
+                write!(f, "{}", e)
+            }
+        };
+        let prec = self.precedence();
+        match self {
+            Expr::Empty | Expr::Just(Symbol::Empty) => write!(f, "{}", EPSILON),
+            // Characters the parser would read as operators, or as the start of a class or an escape, are escaped.
+            Expr::Just(Symbol::Char(c)) if is_special(*c) => write!(f, "\\{}", c),
+            Expr::Just(Symbol::Char(c)) => write!(f, "{}", c),
+            Expr::Class(class) => write_class(f, class),
+            Expr::Or(e1, e2) => {
+                sub(f, e1, prec)?;
+                write!(f, "|")?;
+                sub(f, e2, prec)
+            }
+            Expr::And(e1, e2) => {
+                sub(f, e1, prec)?;
+                sub(f, e2, prec)
+            }
+            Expr::Intersect(e1, e2) => {
+                sub(f, e1, prec)?;
+                write!(f, "&")?;
+                sub(f, e2, prec)
+            }
+            Expr::Star(e) => {
+                sub(f, e, prec)?;
+                write!(f, "*")
+            }
+            Expr::Plus(e) => {
+                sub(f, e, prec)?;
+                write!(f, "+")
+            }
+            Expr::QMark(e) => {
+                sub(f, e, prec)?;
+                write!(f, "?")
+            }
+            Expr::Complement(e) => {
+                write!(f, "~")?;
+                sub(f, e, prec)
+            }
+            Expr::Group(Some(name), e) => write!(f, "(?<{}>{})", name, e),
+            Expr::Group(None, e) => write!(f, "({})", e),
+        }
+    }
+}
+
+pub fn parse_string_to_expr(s: &str) -> Option<Expr> {
+    let mut expstack: Vec<Expr> = Vec::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                // ! The expression popped second comes first in the chronological order.
+                let sym2 = expstack.pop()?;
+                let sym1 = expstack.pop()?;
+                expstack.push(Expr::And(Box::new(sym1), Box::new(sym2)));
+            }
+            '|' => {
+                let sym2 = expstack.pop()?;
+                let sym1 = expstack.pop()?;
+                expstack.push(Expr::Or(Box::new(sym1), Box::new(sym2)));
+            }
+            '*' => {
+                let sym = expstack.pop()?;
+                expstack.push(Expr::Star(Box::new(sym)));
+            }
+            '+' => {
+                let sym = expstack.pop()?;
+                expstack.push(Expr::Plus(Box::new(sym)));
+            }
+            '?' => {
+                let sym = expstack.pop()?;
+                expstack.push(Expr::QMark(Box::new(sym)));
+            }
+            '&' => {
+                let sym2 = expstack.pop()?;
+                let sym1 = expstack.pop()?;
+                expstack.push(Expr::Intersect(Box::new(sym1), Box::new(sym2)));
+            }
+            '~' => {
+                let sym = expstack.pop()?;
+                expstack.push(Expr::Complement(Box::new(sym)));
+            }
+            // The parser leaves a closing parenthesis after each group's operand, so it can be kept.
+            ')' => {
+                let sym = expstack.pop()?;
+                expstack.push(Expr::Group(None, Box::new(sym)));
+            }
+            // A backslash makes the character after it a literal, even an operator.
+            '\\' => expstack.push(Expr::Just(Symbol::Char(chars.next()?))),
+            EPSILON => expstack.push(Expr::Empty),
+            '[' => expstack.push(Expr::Class(parse_class(&mut chars)?)),
+            c if ASCII.contains(&c) => {
+                expstack.push(Expr::Just(Symbol::Char(c)));
+            }
+            // A character outside the alphabet, which the parser passes through.
+            _ => return None,
+        }
+    }
+    // The last expr on expstack is the expression we want.
+    // If not, then expr parse error.
+    if expstack.len() != 1 {
+        None
+    } else {
+        expstack.pop()
+    }
+}
+
+// Parse an Expression into a recursive set of FAPieces.
+// ! Super inefficient... it just reads redundant transitions over and over again.  STOP CREATING NEW FAPIECES!  JUST USE THE OLD ONES!
+// DETERMINE A WAY TO CONSTANT TIME APPEND STATES AND TRANSITIONS, INSTEAD OF ITERATING
+fn parse(expr: Expr, record: &mut Record) -> FAPiece {
+    // The label is only worked out when fragments are kept, and before the expression is taken apart.
+    let label = match (&record.fragments, &expr) {
+        (
+            Some(_),
+            Expr::Or(..)
+            | Expr::And(..)
+            | Expr::Star(_)
+            | Expr::Plus(_)
+            | Expr::QMark(_)
+            | Expr::Intersect(..)
+            | Expr::Complement(_),
+        ) => Some(expr.to_string()),
+        _ => None,
+    };
+    let first = record.fragments.as_ref().map_or(0, Vec::len);
+
+    let piece = build(expr, record);
+
+    if let (Some(label), Some(fragments)) = (label, record.fragments.as_mut()) {
+        // Fragments are listed after the ones inside them, so the fragments listed since this one's operands began
+        // are its descendants, and those not yet taken by another are its children.
+        let id = fragments.len();
+        let mut children = Vec::new();
+        for (i, fragment) in fragments.iter_mut().enumerate().skip(first) {
+            if fragment.parent.is_none() {
+                fragment.parent = Some(id);
+                children.push(i);
+            }
+        }
+        fragments.push(Fragment {
+            label,
+            states: piece.states(),
+            children,
+            parent: None,
+        });
+    }
+
+    piece
+}
+
+// With `groups` in the record, each group gets states of its own to open and close it, which are listed there;
+// without, groups are built as what they contain.
+fn build(expr: Expr, record: &mut Record) -> FAPiece {
+    // Match on an expression, turning it into a single finite automata.
+    // This is done by recursing through the expression and building the piece bit by bit.
+    match expr {
+        Expr::Empty => FAPiece::just_sym(Symbol::Empty),
+        Expr::Just(sym) => FAPiece::just_sym(sym),
+        Expr::Class(class) => {
+            let start = FAPiece::produce_id();
+            let end = FAPiece::produce_id();
+
+            let mut class_piece = FAPiece::new_with_start_end(start, end);
+            for c in class {
+                class_piece.add_transition(Transition::from(Symbol::Char(c), start, end));
+            }
+
+            class_piece
+        }
+        Expr::Or(e1, e2) => {
+            let fa_piece1 = parse(*e1, record);
+            let fa_piece2 = parse(*e2, record);
+
+            let start = FAPiece::produce_id();
+            let end = FAPiece::produce_id();
+
+            let mut or_piece = FAPiece::new_with_start_end(start, end);
+
+            fa_piece1
+                .states()
+                .iter()
+                .for_each(|&state| or_piece.add_state(state));
+            fa_piece2
+                .states()
+                .iter()
+                .for_each(|&state| or_piece.add_state(state));
+            fa_piece1
+                .delta()
+                .iter()
+                .for_each(|&trans| or_piece.add_transition(trans));
+            fa_piece2
+                .delta()
+                .iter()
+                .for_each(|&trans| or_piece.add_transition(trans));
+
+            or_piece.add_transition(Transition::from(Symbol::Empty, start, fa_piece1.start()));
+            or_piece.add_transition(Transition::from(Symbol::Empty, start, fa_piece2.start()));
+            or_piece.add_transition(Transition::from(Symbol::Empty, fa_piece1.end(), end));
+            or_piece.add_transition(Transition::from(Symbol::Empty, fa_piece2.end(), end));
+
+            or_piece
+        }
+        Expr::And(e1, e2) => {
+            let fa_piece1 = parse(*e1, record);
+            let second = record.fragments.as_ref().map_or(0, Vec::len);
+            let mut fa_piece2 = parse(*e2, record);
+
+            let mut and_piece = FAPiece::new_with_start_end(fa_piece1.start(), fa_piece2.end());
+
+            // Connect the two pieces, removing old states and adding new states and adjusting transiitons.
+            let oldstart = fa_piece2.start();
+            let newstart = fa_piece1.end();
+
+            fa_piece2.remove_state(fa_piece2.start());
+            record.forget(second, |states| {
+                states.remove(&oldstart);
+            });
+            fa_piece2.add_state(fa_piece1.end());
+            fa_piece2.set_start(fa_piece1.end());
+
+            // Change all the transitions so that they use the new starting state.  Both ends are checked, as a piece
+            // spliced in from a DFA can loop on its starting state.
+            fa_piece2.delta_mut().iter_mut().for_each(|trans| {
+                if trans.start() == oldstart {
+                    trans.set_start(newstart);
+                }
+                if trans.end() == oldstart {
+                    trans.set_end(newstart)
+                }
+            });
+
+            fa_piece1
+                .states()
+                .iter()
+                .for_each(|&state| and_piece.add_state(state));
+            fa_piece2
+                .states()
+                .iter()
+                .for_each(|&state| and_piece.add_state(state));
+            fa_piece1
+                .delta()
+                .iter()
+                .for_each(|&trans| and_piece.add_transition(trans));
+            fa_piece2
+                .delta()
+                .iter()
+                .for_each(|&trans| and_piece.add_transition(trans));
+
+            and_piece
+        }
+        Expr::Star(e) => {
+            let fa_piece = parse(*e, record);
+
+            let start = FAPiece::produce_id();
+            let end = FAPiece::produce_id();
+
+            let mut star_piece = FAPiece::new_with_start_end(start, end);
+
+            fa_piece
+                .states()
+                .iter()
+                .for_each(|&state| star_piece.add_state(state));
+            fa_piece
+                .delta()
+                .iter()
+                .for_each(|&trans| star_piece.add_transition(trans));
+
+            // Each state's transitions are kept in order, and matching that prefers earlier ones is greedy if
+            // going around the loop again comes before leaving it.
+            star_piece.add_transition(Transition::from(Symbol::Empty, start, fa_piece.start()));
+            star_piece.add_transition(Transition::from(Symbol::Empty, start, end));
+            star_piece.add_transition(Transition::from(
+                Symbol::Empty,
+                fa_piece.end(),
+                fa_piece.start(),
+            ));
+            star_piece.add_transition(Transition::from(Symbol::Empty, fa_piece.end(), end));
+
+            star_piece
+        }
+        Expr::Plus(e) => {
+            let fa_piece = parse(*e, record);
+
+            let start = FAPiece::produce_id();
+            let end = FAPiece::produce_id();
+
+            let mut plus_piece = FAPiece::new_with_start_end(start, end);
+
+            fa_piece
+                .states()
+                .iter()
+                .for_each(|&state| plus_piece.add_state(state));
+            fa_piece
+                .delta()
+                .iter()
+                .for_each(|&trans| plus_piece.add_transition(trans));
+
+            plus_piece.add_transition(Transition::from(Symbol::Empty, start, fa_piece.start()));
+            // plus_piece.add_transition(Transition::from(Symbol::Empty, start, end));
+            // Looping before leaving, as for the star.
+            plus_piece.add_transition(Transition::from(
+                Symbol::Empty,
+                fa_piece.end(),
+                fa_piece.start(),
+            ));
+            plus_piece.add_transition(Transition::from(Symbol::Empty, fa_piece.end(), end));
+
+            plus_piece
+        }
+        Expr::QMark(e) => {
+            let fa_piece = parse(*e, record);
+
+            let start = FAPiece::produce_id();
+            let end = FAPiece::produce_id();
+
+            let mut qmark_piece = FAPiece::new_with_start_end(start, end);
+
+            fa_piece
+                .states()
+                .iter()
+                .for_each(|&state| qmark_piece.add_state(state));
+            fa_piece
+                .delta()
+                .iter()
+                .for_each(|&trans| qmark_piece.add_transition(trans));
+
+            qmark_piece.add_transition(Transition::from(Symbol::Empty, start, fa_piece.start()));
+            qmark_piece.add_transition(Transition::from(Symbol::Empty, fa_piece.end(), end));
+            qmark_piece.add_transition(Transition::from(Symbol::Empty, start, end));
+            // qmark_piece.add_transition(Transition::from(
+            //     Symbol::Empty,
+            //     fa_piece.end(),
+            //     fa_piece.start(),
+            // ));
+
+            qmark_piece
+        }
+        // Thompson's construction has no pieces for these, so the operands are built into automata and combined
+        // through the product construction; the minimal DFA of the result is then spliced back in as a piece.
+        Expr::Intersect(e1, e2) => {
+            let first = record.fragments.as_ref().map_or(0, Vec::len);
+            let fa1 = fapiece_to_fa(parse(*e1, record));
+            let fa2 = fapiece_to_fa(parse(*e2, record));
+            record.forget(first, StateSet::clear);
+
+            let mut intersect = fa1.intersection(&fa2);
+            intersect.minimize();
+            fa_to_fapiece(&intersect)
+        }
+        Expr::Complement(e) => {
+            let first = record.fragments.as_ref().map_or(0, Vec::len);
+            let fa = fapiece_to_fa(parse(*e, record));
+            record.forget(first, StateSet::clear);
+
+            let mut complement = fa.complement(&ASCII);
+            complement.minimize();
+            fa_to_fapiece(&complement)
+        }
+        Expr::Group(name, e) => {
+            let list = match record.groups.as_mut() {
+                Some(list) => list,
+                None => return parse(*e, record),
+            };
+            // A concatenation merges the start of its right piece away, so the group is entered through a start of
+            // its own rather than its opening state.
+            let start = FAPiece::produce_id();
+            let open = FAPiece::produce_id();
+            let close = FAPiece::produce_id();
+            // The group is listed before its contents are built, so the groups inside it come after it.
+            list.push(GroupStates { name, open, close });
+            let fa_piece = parse(*e, record);
+
+            let mut group_piece = FAPiece::new_with_start_end(start, close);
+            group_piece.add_state(open);
+            fa_piece
+                .states()
+                .iter()
+                .for_each(|&state| group_piece.add_state(state));
+            fa_piece
+                .delta()
+                .iter()
+                .for_each(|&trans| group_piece.add_transition(trans));
+
+            group_piece.add_transition(Transition::from(Symbol::Empty, start, open));
+            group_piece.add_transition(Transition::from(Symbol::Empty, open, fa_piece.start()));
+            group_piece.add_transition(Transition::from(Symbol::Empty, fa_piece.end(), close));
+
+            group_piece
+        }
+    }
+}
+
+// Copies an automaton into a piece under fresh state ids.  The piece's end is a new state that every accepting state
+// reaches through an empty transition.
+fn fa_to_fapiece(fa: &FA) -> FAPiece {
+    let mut ids = HashMap::new();
+    for t in fa.delta() {
+        for s in [t.start(), t.end()] {
+            ids.entry(s).or_insert_with(FAPiece::produce_id);
+        }
+    }
+    let start = *ids.entry(fa.starting()).or_insert_with(FAPiece::produce_id);
+    let end = FAPiece::produce_id();
+
+    let mut piece = FAPiece::new_with_start_end(start, end);
+    ids.values().for_each(|&state| piece.add_state(state));
+    for t in fa.delta() {
+        piece.add_transition(Transition::from(t.sym(), ids[&t.start()], ids[&t.end()]));
+    }
+    for a in fa.accepting() {
+        if let Some(&state) = ids.get(a) {
+            piece.add_transition(Transition::from(Symbol::Empty, state, end));
+        }
+    }
+
+    piece
+}
+
+fn fapiece_to_fa(construction: FAPiece) -> FA {
+    let mut fa = FA::new();
+
+    for state in construction.states().clone().into_iter() {
+        fa.add_state(state);
+    }
+    fa.set_start(construction.start());
+    fa.add_acceptor(construction.end());
+    for transition in construction.delta() {
+        fa.add_transition(*transition);
+    }
+
+    fa
+}
+
+pub fn parse_to_finite_automata(input: &str) -> Option<FA> {
+    let expr = parse_string_to_expr(input)?;
+    Some(expr_to_finite_automata(expr))
+}
+
+pub fn expr_to_finite_automata(expr: Expr) -> FA {
+    fapiece_to_fa(parse(expr, &mut Record::default()))
+}
+
+// What `parse` lists besides the automaton, for the parts of it that are kept.
+#[derive(Debug, Default)]
+struct Record {
+    groups: Option<Vec<GroupStates>>,
+    fragments: Option<Vec<Fragment>>,
+}
+
+impl Record {
+    // Takes states that did not make it into the automaton out of the fragments listed from `first` on.
+    fn forget(&mut self, first: usize, forget: impl Fn(&mut StateSet<State>)) {
+        if let Some(fragments) = self.fragments.as_mut() {
+            fragments
+                .iter_mut()
+                .skip(first)
+                .for_each(|fragment| forget(&mut fragment.states));
+        }
+    }
+}
+
+// The states where a group of a tagged NFA opens and closes: reaching them is what marks where the group's match
+// starts and ends.
+#[derive(Debug, Clone)]
+pub struct GroupStates {
+    pub name: Option<String>,
+    pub open: State,
+    pub close: State,
+}
+
+// Thompson's construction with a pair of states added around each group, which are listed in the order of the groups'
+// opening parentheses.  `&` and `~` are built through DFAs, which would lose the states of any group inside them, so
+// such groups are an error.
+pub fn expr_to_tagged_nfa(expr: Expr) -> Result<(FA, Vec<GroupStates>), String> {
+    if groups_lost(&expr, false) {
+        return Err("groups inside & or ~ cannot capture".to_string());
+    }
+    let mut record = Record {
+        groups: Some(Vec::new()),
+        fragments: None,
+    };
+    let fa = fapiece_to_fa(parse(expr, &mut record));
+    Ok((fa, record.groups.unwrap_or_default()))
+}
+
+// Whether a group sits inside `&` or `~`; `inside` says whether this expression already does.
+fn groups_lost(expr: &Expr, inside: bool) -> bool {
+    match expr {
+        Expr::Empty | Expr::Just(_) | Expr::Class(_) => false,
+        Expr::Group(_, e) => inside || groups_lost(e, inside),
+        Expr::Or(e1, e2) | Expr::And(e1, e2) => groups_lost(e1, inside) || groups_lost(e2, inside),
+        Expr::Star(e) | Expr::Plus(e) | Expr::QMark(e) => groups_lost(e, inside),
+        Expr::Intersect(e1, e2) => groups_lost(e1, true) || groups_lost(e2, true),
+        Expr::Complement(e) => groups_lost(e, true),
+    }
+}
+
+// The piece Thompson's construction built for one operator of the regex, with the subexpression it came from.  A
+// fragment's states include those of the fragments inside it, and are all states of the automaton: a concatenation's
+// right operand loses its starting state to the end of its left one.
+#[derive(Debug, Clone)]
+pub struct Fragment {
+    pub label: String,
+    pub states: StateSet<State>,
+    // The fragments directly inside this one, as indices into the list of fragments.
+    pub children: Vec<usize>,
+    pub parent: Option<usize>,
+}
+
+// Thompson's construction with the fragment for each `|`, concatenation, `*`, `+`, `?`, `&` and `~` listed, each
+// after the fragments inside it.  The operands of `&` and `~` are built through DFAs, so their fragments have no
+// states.
+pub fn expr_to_nfa_with_fragments(expr: Expr) -> (FA, Vec<Fragment>) {
+    let mut record = Record {
+        groups: None,
+        fragments: Some(Vec::new()),
+    };
+    let fa = fapiece_to_fa(parse(expr, &mut record));
+    (fa, record.fragments.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivalence::{equivalence, Equivalence};
+    use crate::regex_parser::{parse_to_expr, parse_to_nfa};
+
+    #[test]
+    fn operator_characters_print_escaped() {
+        for regex in ["\\*a", "\\(\\)|\\|", "\\\\\\.", "\\&\\~", "\\ε", "\\[a]", "\\?+", "[*+](\\+)"] {
+            let expr = parse_to_expr(regex).unwrap();
+            let printed = expr.to_string();
+            assert_eq!(parse_to_expr(&printed), Some(expr), "{} printed as {}", regex, printed);
+        }
+        let star = Expr::Star(Box::new(Expr::Just(Symbol::Char('*'))));
+        assert_eq!(star.to_string(), "\\**");
+    }
+
+    #[test]
+    fn regex_of_operator_symbols_parses_back() {
+        let mut fa = FA::new();
+        for s in 0..3 {
+            fa.add_state(s);
+        }
+        for (c, p, q) in [('*', 0, 1), ('|', 1, 2), ('(', 1, 2), ('.', 2, 2), ('~', 2, 0)] {
+            fa.add_transition(Transition::from(Symbol::Char(c), p, q));
+        }
+        fa.add_acceptor(2);
+        fa.set_start(0);
+
+        let regex = fa.to_regex().unwrap().to_string();
+        let parsed = parse_to_nfa(&regex).unwrap();
+        assert_eq!(equivalence(&parsed, &fa), Equivalence::Equivalent, "{}", regex);
+    }
+
+    // The minimal DFAs spliced in for `&` and `~` can loop on their starting state, which a concatenation merges away.
+    #[test]
+    fn concatenation_keeps_loops_on_a_spliced_start() {
+        for (regex, same) in [
+            ("x(a*&a*)", "xa*"),
+            ("x((a|b)*&(b|a)*)y", "x(a|b)*y"),
+            ("x~(~(a*))", "xa*"),
+        ] {
+            let (fa, other) = (parse_to_nfa(regex).unwrap(), parse_to_nfa(same).unwrap());
+            assert_eq!(equivalence(&fa, &other), Equivalence::Equivalent, "{}", regex);
+        }
+    }
+
+    #[test]
+    fn fragment_tree() {
+        let (fa, fragments) = expr_to_nfa_with_fragments(parse_to_expr("(a|b)*abb").unwrap());
+        let labels = fragments.iter().map(|f| f.label.as_str()).collect::<Vec<&str>>();
+        assert_eq!(labels, vec!["a|b", "(a|b)*", "(a|b)*a", "(a|b)*ab", "(a|b)*abb"]);
+        for (i, fragment) in fragments.iter().enumerate() {
+            let children: Vec<usize> = if i == 0 { vec![] } else { vec![i - 1] };
+            assert_eq!(fragment.children, children);
+            assert_eq!(fragment.parent, (i + 1 < fragments.len()).then_some(i + 1));
+            assert!(fragment.states.iter().all(|s| fa.states().contains(s)));
+        }
+        // The outermost fragment is the whole automaton, and each holds the one inside it.
+        assert_eq!(fragments[4].states.len(), fa.num_states());
+        for pair in fragments.windows(2) {
+            assert!(pair[0].states.is_subset(&pair[1].states));
+        }
+        // a|b and its star, then one state more for each character concatenated after them.
+        let sizes = fragments.iter().map(|f| f.states.len()).collect::<Vec<usize>>();
+        assert_eq!(sizes, vec![6, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn fragments_inside_intersection_have_no_states() {
+        let (fa, fragments) = expr_to_nfa_with_fragments(parse_to_expr("(a|b)&a*c").unwrap());
+        for fragment in &fragments {
+            assert!(fragment.states.iter().all(|s| fa.states().contains(s)));
+        }
+        let outer = fragments.last().unwrap();
+        assert_eq!(outer.label, "(a|b)&a*c");
+        assert_eq!(outer.states.len(), fa.num_states());
+        assert!(fragments[..fragments.len() - 1].iter().all(|f| f.states.is_empty()));
+    }
+}